@@ -1,4 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::export::{ExportFormat, ImportFormat};
+use crate::graph::GraphFormat;
+use crate::output::OutputFormat;
+use crate::sync::SyncPrefer;
+use crate::theme::ColorChoice;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "theca")]
@@ -31,6 +37,44 @@ pub struct Cli {
     /// Do not ask for confirmation
     #[arg(short, long)]
     pub yes: bool,
+
+    /// Output format when viewing a note or listing notes
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Template for viewing a note, or for each row when listing notes
+    #[arg(long)]
+    pub template: Option<String>,
+
+    /// Time zone to show dates in: local, utc, a name like Europe/Berlin or
+    /// an offset like +02:00
+    #[arg(long, global = true, env = "THECA_TZ")]
+    pub tz: Option<String>,
+
+    /// Show dates relative to now, e.g. "3h ago" or "in 2 days"
+    #[arg(long, global = true)]
+    pub relative: bool,
+
+    /// Print a note body as it is stored instead of rendering its markdown
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Never send long output through $PAGER
+    #[arg(long, global = true)]
+    pub no_pager: bool,
+
+    /// When to use colors
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+}
+
+/// What deleting a note does with its children
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ChildAction {
+    /// Delete them too
+    Delete,
+    /// Keep them, they move up to the deleted note's parent
+    Keep,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -40,7 +84,7 @@ pub enum Commands {
         /// Title of the note
         title: String,
 
-        /// Body of the note
+        /// Body of the note, `-` to read it from stdin
         #[arg(default_value = "")]
         body: String,
 
@@ -51,6 +95,26 @@ pub enum Commands {
         /// Use editor to write body
         #[arg(short, long)]
         editor: bool,
+
+        /// ID of the note this one is part of
+        #[arg(long)]
+        parent: Option<usize>,
+
+        /// Due date, YYYY-MM-DD or 'YYYY-MM-DD HH:MM' in local time
+        #[arg(long)]
+        due: Option<String>,
+
+        /// Tag the note, can be given more than once
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Start from a template in the profile folder's templates folder
+        #[arg(long, conflicts_with = "body")]
+        template: Option<String>,
+
+        /// Value of a template's `{ask:name}` placeholder
+        #[arg(long = "var", value_name = "NAME=VALUE", requires = "template")]
+        vars: Vec<String>,
     },
     
     /// Edit an existing note
@@ -58,12 +122,16 @@ pub enum Commands {
         /// ID of the note to edit
         id: usize,
 
+        /// `-` to read the new body from stdin
+        #[arg(value_name = "-", value_parser = ["-"])]
+        input: Option<String>,
+
         /// New title
         #[arg(short, long)]
         title: Option<String>,
-        
-        /// New body
-        #[arg(short, long)]
+
+        /// New body, `-` to read it from stdin
+        #[arg(short, long, conflicts_with = "input")]
         body: Option<String>,
 
         /// New status
@@ -73,6 +141,94 @@ pub enum Commands {
         /// Use editor
         #[arg(short, long)]
         editor: bool,
+
+        /// Add the new body to the end of the existing one
+        #[arg(short, long)]
+        append: bool,
+
+        /// New due date, YYYY-MM-DD or 'YYYY-MM-DD HH:MM' in local time,
+        /// `none` removes it
+        #[arg(long)]
+        due: Option<String>,
+
+        /// Add a tag, can be given more than once
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Remove a tag, can be given more than once
+        #[arg(long = "untag", value_name = "TAG")]
+        untags: Vec<String>,
+    },
+
+    /// Add text to the end of a note's body
+    Append {
+        /// ID of the note
+        id: usize,
+
+        /// Text to add, `-` to read it from stdin
+        #[arg(allow_hyphen_values = true)]
+        text: String,
+    },
+
+    /// Add text to the start of a note's body
+    Prepend {
+        /// ID of the note
+        id: usize,
+
+        /// Text to add, `-` to read it from stdin
+        #[arg(allow_hyphen_values = true)]
+        text: String,
+    },
+
+    /// Substitute text in a note's body
+    Sed {
+        /// ID of the note
+        id: usize,
+
+        /// Substitution like 's/old/new/', `old` is a regex. the flags `g`
+        /// replace every match and `i` ignores case
+        expr: String,
+    },
+
+    /// Replace a line of a note's body
+    ReplaceLine {
+        /// ID of the note
+        id: usize,
+
+        /// Number of the line, counting from 1
+        line: usize,
+
+        /// New text of the line
+        #[arg(allow_hyphen_values = true)]
+        text: String,
+    },
+
+    /// Check items of a note's checklist
+    Check {
+        /// ID of the note
+        id: usize,
+
+        /// Numbers of the items, counting from 1
+        #[arg(required = true)]
+        items: Vec<usize>,
+
+        /// Set the status from the checklist, started or done
+        #[arg(long)]
+        auto_status: bool,
+    },
+
+    /// Uncheck items of a note's checklist
+    Uncheck {
+        /// ID of the note
+        id: usize,
+
+        /// Numbers of the items, counting from 1
+        #[arg(required = true)]
+        items: Vec<usize>,
+
+        /// Set the status from the checklist, started or done
+        #[arg(long)]
+        auto_status: bool,
     },
 
     /// Delete a note
@@ -80,10 +236,60 @@ pub enum Commands {
         /// ID(s) of the note to delete
         #[arg(required = true)]
         id: Vec<usize>,
+
+        /// What happens to the notes' children, asked when not given
+        #[arg(long, value_enum)]
+        children: Option<ChildAction>,
+    },
+
+    /// Move a note under another note, or back to the top level
+    Move {
+        /// ID of the note to move
+        id: usize,
+
+        /// ID of the new parent
+        #[arg(long, required_unless_present = "root", conflicts_with = "root")]
+        parent: Option<usize>,
+
+        /// Make it a top level note
+        #[arg(long)]
+        root: bool,
     },
     
+    /// List the links from and to a note
+    Links {
+        /// ID of the note
+        id: usize,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Report links to notes that don't exist
+    CheckLinks {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Print the notes and their links, parents and shared tags as a graph
+    Graph {
+        /// Graph format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+
+        /// Filter by status
+        #[arg(long)]
+        status: Option<String>,
+    },
+
     /// List profiles
-    ListProfiles,
+    ListProfiles {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
 
     /// Create a new profile
     NewProfile {
@@ -125,10 +331,30 @@ pub enum Commands {
         /// Limit results
         #[arg(short, long)]
         limit: Option<usize>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Template for each row, e.g. '{id:>4} {status:1} {title}'
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Sort keys, e.g. 'status,-touched,title' ('-' sorts descending)
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Columns to show, from id,title,status,touched,created,due,tags,progress
+        #[arg(long)]
+        columns: Option<String>,
     },
 
     /// Show profile info
-    Info,
+    Info {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
 
     /// Clear all notes
     Clear,
@@ -147,7 +373,7 @@ pub enum Commands {
         #[arg(short, long)]
         reverse: bool,
 
-        /// Output as YAML
+        /// Output as YAML (same as `--format yaml`)
         #[arg(long)]
         yaml: bool,
 
@@ -158,8 +384,215 @@ pub enum Commands {
         /// Filter by status
         #[arg(long)]
         status: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Template for each row, e.g. '{id:>4} {status:1} {title}'
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Sort keys, e.g. 'status,-touched,title' ('-' sorts descending)
+        #[arg(long)]
+        sort: Option<String>,
+
+        /// Columns to show, from id,title,status,touched,created,due,tags,progress
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// Show children indented under their parents
+        #[arg(long)]
+        tree: bool,
     },
 
     /// Sync profile with markdown files
-    Sync,
+    Sync {
+        /// Only print what would be done
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Settle notes changed in both the profile and their file
+        #[arg(long, value_enum)]
+        prefer: Option<SyncPrefer>,
+
+        /// Keep running and sync again whenever the files change. Other writers
+        /// are noticed by modification time, to the second, there is no lock
+        #[arg(short, long, conflicts_with = "dry_run")]
+        watch: bool,
+    },
+
+    /// Version the profile with git and share it through a remote
+    Git {
+        #[command(subcommand)]
+        action: GitCommands,
+    },
+
+    /// Browse and change notes in a full screen view
+    Tui,
+
+    /// Run commands at a prompt against a profile that is loaded once
+    Shell {
+        /// Save after every command instead of on `save` and exit
+        #[arg(long)]
+        autosave: bool,
+    },
+
+    /// Run commands from a file, one per line or as JSON objects, and save once
+    Batch {
+        /// File to read the commands from, `-` for stdin
+        #[arg(default_value = "-")]
+        path: String,
+
+        /// Save nothing if any command fails
+        #[arg(long)]
+        atomic: bool,
+    },
+
+    /// Run or manage saved views
+    #[command(args_conflicts_with_subcommands = true)]
+    View {
+        #[command(subcommand)]
+        action: Option<ViewCommands>,
+
+        /// Name of the saved view to run
+        name: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Template for each row, e.g. '{id:>4} {status:1} {title}'
+        #[arg(long)]
+        template: Option<String>,
+    },
+
+    /// Export notes to CSV, TSV or a static HTML site
+    Export {
+        /// Export format
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// Comma separated fields to export (id, title, status, body, touched, created, due, tags, progress),
+        /// all but progress by default
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// Leave out the header row
+        #[arg(long)]
+        no_header: bool,
+
+        /// File to write to (default: stdout), or the directory for html
+        path: Option<String>,
+    },
+
+    /// Import notes from CSV or TSV
+    Import {
+        /// Import format
+        #[arg(long, value_enum)]
+        format: ImportFormat,
+
+        /// Comma separated fields of a file without a header row
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// File to read from, `-` for stdin
+        path: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum GitCommands {
+    /// Start git history for the profile
+    Init {
+        /// URL of the remote to push to and pull from
+        #[arg(long)]
+        remote: Option<String>,
+    },
+
+    /// Show the history of the profile
+    Log {
+        /// Limit results
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Push the profile to the remote
+    Push,
+
+    /// Pull the profile from the remote, merging notes by id
+    Pull,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ViewCommands {
+    /// Save a named view
+    Save {
+        /// Name of the view
+        name: String,
+
+        /// Save to the user config instead of the profile config
+        #[arg(long)]
+        global: bool,
+
+        #[command(flatten)]
+        view: ViewArgs,
+    },
+
+    /// List saved views
+    List,
+
+    /// Delete a saved view
+    Delete {
+        /// Name of the view
+        name: String,
+
+        /// Delete from the user config instead of the profile config
+        #[arg(long)]
+        global: bool,
+    },
+}
+
+/// Options stored in a saved view
+#[derive(Args, Debug, Clone)]
+pub struct ViewArgs {
+    /// Only show notes matching this pattern
+    #[arg(long)]
+    pub search: Option<String>,
+
+    /// Match the pattern against the body instead of the title
+    #[arg(short = 'b', long)]
+    pub search_body: bool,
+
+    /// Use regex
+    #[arg(long)]
+    pub regex: bool,
+
+    /// Filter by status
+    #[arg(long)]
+    pub status: Option<String>,
+
+    /// Sort by date
+    #[arg(short, long)]
+    pub datesort: bool,
+
+    /// Reverse sort
+    #[arg(short, long)]
+    pub reverse: bool,
+
+    /// Limit results
+    #[arg(short, long)]
+    pub limit: Option<usize>,
+
+    /// Condensed output
+    #[arg(short, long)]
+    pub condensed: bool,
+
+    /// Sort keys, e.g. 'status,-touched,title' ('-' sorts descending)
+    #[arg(long)]
+    pub sort: Option<String>,
+
+    /// Columns to show, from id,title,status,touched,created,due,tags,progress
+    #[arg(long)]
+    pub columns: Option<String>,
 }
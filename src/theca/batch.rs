@@ -0,0 +1,158 @@
+// batch.rs
+//   `theca batch`, commands read from a file or stdin and run against one
+//   loaded profile that is saved once at the end. a line is either the
+//   arguments of a theca command or a JSON object describing an operation.
+
+use std::fs::File;
+use std::io::{stdin, Read};
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::args::{Cli, Commands, ViewCommands};
+use crate::errors::Result;
+use crate::shell::line_cli;
+use crate::{execute, specific_fail, Session};
+
+/// An operation given as a JSON line, e.g.
+/// `{"op": "edit", "id": 3, "status": "done"}`
+#[derive(Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Op {
+    Add {
+        title: String,
+        #[serde(default)]
+        body: String,
+        status: Option<String>,
+        parent: Option<usize>,
+        due: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    Edit {
+        id: usize,
+        title: Option<String>,
+        body: Option<String>,
+        status: Option<String>,
+        #[serde(default)]
+        append: bool,
+        due: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        untags: Vec<String>,
+    },
+    Del {
+        id: usize,
+    },
+    Transfer {
+        id: usize,
+        to: String,
+    },
+}
+
+impl From<Op> for Commands {
+    fn from(op: Op) -> Commands {
+        match op {
+            Op::Add { title, body, status, parent, due, tags } => {
+                Commands::Add { title, body, status, editor: false, parent, due, tags, template: None, vars: vec![] }
+            }
+            Op::Edit { id, title, body, status, append, due, tags, untags } => {
+                Commands::Edit { id, input: None, title, body, status, editor: false, append, due, tags, untags }
+            }
+            Op::Del { id } => Commands::Del { id: vec![id], children: None },
+            Op::Transfer { id, to } => Commands::Transfer { id, target_profile: to },
+        }
+    }
+}
+
+/// the commands of a batch with their line numbers. blank lines and lines
+/// starting with `#` are skipped. every line is checked before any of them
+/// runs.
+pub fn parse(cli: &Cli, text: &str) -> Result<Vec<(usize, Cli)>> {
+    let mut commands = vec![];
+    for (n, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = if line.starts_with('{') {
+            let op: Op = serde_json::from_str(line).map_err(|e| format!("line {}: {}", n, e))?;
+            Cli { command: Some(op.into()), ..Cli::parse_from(["theca"]) }
+        } else {
+            let words = shlex::split(line).ok_or_else(|| format!("line {}: unbalanced quotes", n))?;
+            match Cli::try_parse_from(std::iter::once("theca".to_string()).chain(words)) {
+                Ok(p) => p,
+                Err(e) => {
+                    let message = e.to_string();
+                    let message = message.lines().next().unwrap_or_default().trim_start_matches("error: ");
+                    return specific_fail!(format!("line {}: {}", n, message));
+                }
+            }
+        };
+        let parsed = line_cli(cli, parsed).map_err(|e| format!("line {}: {}", n, e))?;
+        // the prompt would read the rest of the batch, or loop at the end of it
+        if matches!(parsed.command, Some(Commands::Clear)) && !parsed.yes {
+            return specific_fail!(format!("line {}: clear needs --yes in a batch", n));
+        }
+        commands.push((n, parsed));
+    }
+    Ok(commands)
+}
+
+/// run the batch in `path`, `-` for stdin. without `atomic` commands that
+/// fail are reported and the rest is saved.
+pub fn run(cli: &Cli, mut session: Session, path: &str, atomic: bool) -> Result<()> {
+    let mut text = String::new();
+    if path == "-" {
+        stdin().read_to_string(&mut text)?;
+    } else {
+        File::open(path)?.read_to_string(&mut text)?;
+    }
+    let commands = parse(cli, &text)?;
+    if atomic {
+        let written = commands.iter().find(|(_, c)| c.command.as_ref().is_some_and(writes_to_disk));
+        if let Some((n, _)) = written {
+            return specific_fail!(format!("line {}: this command can't be undone, it can't be part of an atomic batch", n));
+        }
+    }
+
+    let mut failed = 0;
+    for (n, command) in commands.iter() {
+        match execute(command, &mut session) {
+            Ok(out) => print!("{}", String::from_utf8_lossy(&out)),
+            Err(e) if atomic => return specific_fail!(format!("line {}: {}, nothing was saved", n, e)),
+            Err(e) => {
+                eprintln!("line {}: {}", n, e);
+                failed += 1;
+            }
+        }
+    }
+    if !session.unsaved.is_empty() {
+        session.save(cli)?;
+        println!("saved '{}'", cli.profile);
+    }
+    if failed > 0 {
+        return specific_fail!(format!("{} of {} commands failed", failed, commands.len()));
+    }
+    Ok(())
+}
+
+// whether a command writes to disk before the batch is done, outside of
+// the profile that is saved at the end
+fn writes_to_disk(command: &Commands) -> bool {
+    match command {
+        Commands::Transfer { .. } | Commands::NewProfile { .. } | Commands::EncryptProfile { .. } |
+        Commands::DecryptProfile | Commands::Git { .. } | Commands::Tui | Commands::Shell { .. } |
+        Commands::Batch { .. } => true,
+        Commands::Sync { dry_run, .. } => !dry_run,
+        Commands::Export { path, .. } => path.is_some(),
+        Commands::View { action, .. } => {
+            matches!(action, Some(ViewCommands::Save { .. }) | Some(ViewCommands::Delete { .. }))
+        }
+        Commands::Add { .. } | Commands::Edit { .. } | Commands::Append { .. } | Commands::Prepend { .. } |
+        Commands::Sed { .. } | Commands::ReplaceLine { .. } | Commands::Check { .. } | Commands::Uncheck { .. } |
+        Commands::Del { .. } | Commands::Move { .. } | Commands::Links { .. } | Commands::CheckLinks { .. } |
+        Commands::Graph { .. } | Commands::ListProfiles { .. } | Commands::Search { .. } | Commands::Info { .. } |
+        Commands::Clear | Commands::List { .. } | Commands::Import { .. } => false,
+    }
+}
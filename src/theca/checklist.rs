@@ -0,0 +1,90 @@
+// checklist.rs
+//   GitHub style task lists in note bodies, `- [ ] item` and `- [x] item`.
+//   items are numbered from 1 in the order they appear, items in fenced
+//   code blocks don't count.
+
+use regex::Regex;
+
+use crate::errors::Result;
+use crate::item::Status;
+
+/// A checklist item of a body
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    /// the line of the body it is on, counting from 0
+    pub line: usize,
+    pub checked: bool,
+    pub text: String,
+}
+
+fn task_regex() -> Regex {
+    Regex::new(r"^(\s*(?:[-*+]|\d+[.)])\s+\[)([ xX])(\]\s+)(.*)$").unwrap()
+}
+
+/// the checklist items of a body
+pub fn tasks(body: &str) -> Vec<Task> {
+    let re = task_regex();
+    let mut fence: Option<&str> = None;
+    let mut tasks = vec![];
+    for (i, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        match fence {
+            Some(f) if trimmed.starts_with(f) => fence = None,
+            Some(_) => {}
+            None if trimmed.starts_with("```") => fence = Some("```"),
+            None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+            None => {
+                if let Some(c) = re.captures(line) {
+                    tasks.push(Task { line: i, checked: &c[2] != " ", text: c[4].to_string() });
+                }
+            }
+        }
+    }
+    tasks
+}
+
+/// how many items are checked and how many there are, `None` without a
+/// checklist
+pub fn progress(body: &str) -> Option<(usize, usize)> {
+    let tasks = tasks(body);
+    if tasks.is_empty() {
+        None
+    } else {
+        Some((tasks.iter().filter(|t| t.checked).count(), tasks.len()))
+    }
+}
+
+/// the body with item `n` checked or unchecked
+pub fn set_checked(body: &str, n: usize, checked: bool) -> Result<String> {
+    let tasks = tasks(body);
+    let task = match n.checked_sub(1).and_then(|i| tasks.get(i)) {
+        Some(t) => t,
+        None => return Err(format!("there is no item {}, the checklist has {} items", n, tasks.len()).into()),
+    };
+    let mark = if checked { "x" } else { " " };
+    let re = task_regex();
+    let lines: Vec<String> = body.split('\n')
+                                 .enumerate()
+                                 .map(|(i, l)| {
+                                     if i == task.line {
+                                         re.replace(l, format!("${{1}}{}${{3}}${{4}}", mark).as_str()).to_string()
+                                     } else {
+                                         l.to_string()
+                                     }
+                                 })
+                                 .collect();
+    Ok(lines.join("\n"))
+}
+
+/// the status a note with this checklist progress should get: done when
+/// every item is checked and started once some are, urgent notes stay urgent
+/// until they are done
+pub fn auto_status(current: Status, checked: usize, total: usize) -> Status {
+    if total > 0 && checked == total {
+        Status::Done
+    } else if current == Status::Done || (checked > 0 && current == Status::Blank) {
+        Status::Started
+    } else {
+        current
+    }
+}
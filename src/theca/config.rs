@@ -0,0 +1,227 @@
+// config.rs
+//   definition of Config, the optional settings file that lives next to a
+//   profile (`config.yaml` in the profile directory) or in the users config
+//   directory. profile settings take precedence over user settings.
+
+use std::collections::BTreeMap;
+use std::env::var;
+use std::fmt;
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{specific_fail, specific_fail_str};
+use crate::errors::Result;
+use crate::item::{Field, SortKey, Status};
+use crate::profile::ProfileFlags;
+use crate::sync::Mirror;
+use crate::theme::Theme;
+use crate::utils::profile_paths;
+
+/// name of the config file in both the profile and user config directories
+pub static CONFIG_FILE: &str = "config.yaml";
+
+/// Settings read from a `config.yaml`
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub views: BTreeMap<String, View>,
+    #[serde(default, skip_serializing_if = "Templates::is_empty")]
+    pub templates: Templates,
+    /// colors, missing styles use the default theme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    /// where and how the profile is mirrored to markdown files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<Mirror>,
+    /// set the status of a note from its checklist when items are checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_status: Option<bool>,
+}
+
+/// Default output templates, see `template.rs` for the syntax
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+pub struct Templates {
+    /// template for each row of `list`, `search` and views
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    /// template for viewing a single note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<String>,
+}
+
+impl Templates {
+    pub fn is_empty(&self) -> bool {
+        self.list.is_none() && self.view.is_none()
+    }
+}
+
+/// A saved set of list/search options that can be re-run by name
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+pub struct View {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub search_body: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub regex: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub datesort: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reverse: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub limit: usize,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub condensed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<String>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl View {
+    /// the ProfileFlags that `sorted_print` should use for this view
+    pub fn flags(&self) -> Result<ProfileFlags> {
+        Ok(ProfileFlags {
+            condensed: self.condensed,
+            datesort: self.datesort,
+            regex: self.regex,
+            reverse: self.reverse,
+            search_body: self.search_body,
+            sort: parse_option(&self.sort, SortKey::parse_list)?,
+            columns: parse_option(&self.columns, Field::parse_list)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// parse an optional comma separated list, `None` is an empty list
+pub fn parse_option<T>(s: &Option<String>, parse: fn(&str) -> Result<Vec<T>>) -> Result<Vec<T>> {
+    match *s {
+        Some(ref s) => parse(s),
+        None => Ok(vec![]),
+    }
+}
+
+impl fmt::Display for View {
+    /// render the view as the command line options it was saved from
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut opts: Vec<String> = vec![];
+        if let Some(ref s) = self.search {
+            opts.push(format!("--search {:?}", s));
+        }
+        if self.search_body {
+            opts.push("--search-body".to_string());
+        }
+        if self.regex {
+            opts.push("--regex".to_string());
+        }
+        if let Some(s) = self.status {
+            opts.push(format!("--status {}", s.name()));
+        }
+        if self.datesort {
+            opts.push("--datesort".to_string());
+        }
+        if self.reverse {
+            opts.push("--reverse".to_string());
+        }
+        if self.limit != 0 {
+            opts.push(format!("--limit {}", self.limit));
+        }
+        if self.condensed {
+            opts.push("--condensed".to_string());
+        }
+        if let Some(ref s) = self.sort {
+            opts.push(format!("--sort {}", s));
+        }
+        if let Some(ref c) = self.columns {
+            opts.push(format!("--columns {}", c));
+        }
+        write!(f, "{}", opts.join(" "))
+    }
+}
+
+impl Config {
+    /// read a config file, a missing file is an empty config
+    pub fn load(path: &Path) -> Result<Config> {
+        if !path.is_file() {
+            return Ok(Config::default());
+        }
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        if contents.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        serde_yaml::from_str(&contents).map_err(|e| {
+            format!("invalid YAML in {}: {}", path.display(), e).into()
+        })
+    }
+
+    /// write the config file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.exists() {
+                create_dir_all(dir)?;
+            }
+        }
+        let yaml = serde_yaml::to_string(self)?;
+        File::create(path)?.write_all(yaml.as_bytes())?;
+        Ok(())
+    }
+
+    /// path of the user config, `$THECA_CONFIG` or `<config dir>/theca/config.yaml`
+    pub fn user_path() -> Result<PathBuf> {
+        if let Ok(p) = var("THECA_CONFIG") {
+            return Ok(PathBuf::from(p));
+        }
+        match dirs::config_dir() {
+            Some(d) => Ok(d.join("theca").join(CONFIG_FILE)),
+            None => specific_fail_str!("failed to find your config directory"),
+        }
+    }
+
+    /// path of the config for a specific profile
+    pub fn profile_path(profile_name: &str, profile_folder: &Option<String>) -> Result<PathBuf> {
+        let (profile_dir, _) = profile_paths(profile_name, profile_folder)?;
+        Ok(profile_dir.join(CONFIG_FILE))
+    }
+
+    /// the user config with the profile config layered on top of it
+    pub fn merged(profile_name: &str, profile_folder: &Option<String>) -> Result<Config> {
+        let mut config = Config::load(&Config::user_path()?)?;
+        config.merge(Config::load(&Config::profile_path(profile_name, profile_folder)?)?);
+        Ok(config)
+    }
+
+    /// overlay `other` on top of this config, `other` wins on conflicts
+    pub fn merge(&mut self, other: Config) {
+        self.views.extend(other.views);
+        if other.templates.list.is_some() {
+            self.templates.list = other.templates.list;
+        }
+        if other.templates.view.is_some() {
+            self.templates.view = other.templates.view;
+        }
+        if other.theme.is_some() {
+            self.theme = other.theme;
+        }
+        if other.mirror.is_some() {
+            self.mirror = other.mirror;
+        }
+        if other.auto_status.is_some() {
+            self.auto_status = other.auto_status;
+        }
+    }
+}
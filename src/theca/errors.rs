@@ -44,7 +44,7 @@ impl StdError for Error {
 #[macro_export]
 macro_rules! specific_fail {
     ($short:expr) => {{
-        use crate::errors::{Error, ErrorKind};
+        use $crate::errors::{Error, ErrorKind};
         Err(::std::convert::From::from(
             Error {
                 kind: ErrorKind::Generic,
@@ -139,6 +139,46 @@ impl From<serde_yaml::Error> for Error {
    }
 }
 
+impl From<serde_json::Error> for Error {
+   fn from(err: serde_json::Error) -> Error {
+       Error {
+           kind: ErrorKind::Generic,
+           desc: format!("JSON error: {}", err),
+           detail: None,
+       }
+   }
+}
+
+impl From<csv::Error> for Error {
+   fn from(err: csv::Error) -> Error {
+       Error {
+           kind: ErrorKind::Generic,
+           desc: format!("CSV error: {}", err),
+           detail: None,
+       }
+   }
+}
+
+impl From<notify::Error> for Error {
+   fn from(err: notify::Error) -> Error {
+       Error {
+           kind: ErrorKind::Generic,
+           desc: format!("watch error: {}", err),
+           detail: None,
+       }
+   }
+}
+
+impl From<rustyline::error::ReadlineError> for Error {
+   fn from(err: rustyline::error::ReadlineError) -> Error {
+       Error {
+           kind: ErrorKind::Generic,
+           desc: format!("readline error: {}", err),
+           detail: None,
+       }
+   }
+}
+
 // Add generic String error conversion
 impl From<String> for Error {
     fn from(err: String) -> Error {
@@ -0,0 +1,338 @@
+// export.rs
+//   exporting notes to, and importing notes from, formats other than the
+//   profile file itself.
+
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use serde::Serialize;
+
+use crate::errors::Result;
+use crate::item::{Field, Item, Status};
+use crate::profile::Profile;
+use crate::utils::{extract_status, parse_last_touched, DateDisplay};
+
+/// Formats notes can be exported to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    /// a static site in a directory
+    Html,
+}
+
+/// Formats notes can be imported from
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ImportFormat {
+    Csv,
+    Tsv,
+}
+
+fn delimiter(tsv: bool) -> u8 {
+    if tsv {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+/// Result of an import, rows that could not be turned into notes are kept
+/// with their line number instead of aborting the whole import
+#[derive(Default, Debug)]
+pub struct ImportReport {
+    pub imported: Vec<usize>,
+    pub errors: Vec<(u64, String)>,
+}
+
+/// write notes as delimited text, one row per note
+pub fn export_table<W: Write>(output: W,
+                              notes: &[Item],
+                              columns: &[Field],
+                              header: bool,
+                              tsv: bool)
+                              -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+                         .delimiter(delimiter(tsv))
+                         .from_writer(output);
+    if header {
+        writer.write_record(columns.iter().map(|c| c.name()))?;
+    }
+    for n in notes {
+        writer.write_record(columns.iter().map(|c| n.field(*c)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// add a note to the profile for each row of delimited text. `columns`
+/// names the columns of a file without a header row, otherwise the header
+/// row is used and unknown header columns are ignored. ids in the input are
+/// ignored, imported notes always get new ids.
+pub fn import_table<R: Read>(profile: &mut Profile,
+                             input: R,
+                             columns: Option<&[Field]>,
+                             tsv: bool)
+                             -> Result<ImportReport> {
+    let mut reader = csv::ReaderBuilder::new()
+                         .delimiter(delimiter(tsv))
+                         .has_headers(columns.is_none())
+                         .flexible(true)
+                         .from_reader(input);
+    let columns: Vec<Option<Field>> = match columns {
+        Some(c) => c.iter().map(|f| Some(*f)).collect(),
+        None => reader.headers()?.iter().map(|h| h.trim().parse().ok()).collect(),
+    };
+    if !columns.contains(&Some(Field::Title)) {
+        return Err("imported rows need a title column".into());
+    }
+
+    let mut report = ImportReport::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                report.errors.push((line, e.to_string()));
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |p| p.line());
+        match row_to_note(&columns, &record) {
+            Ok(row) => {
+                profile.add_note(&row.title, &[row.body], Some(row.status), false, false)?;
+                if let Some(n) = profile.notes.last_mut() {
+                    if !row.last_touched.is_empty() {
+                        n.last_touched = row.last_touched;
+                    }
+                    if row.created.is_some() {
+                        n.created = row.created;
+                    }
+                    n.due = row.due;
+                    n.tags = row.tags;
+                    report.imported.push(n.id);
+                }
+            }
+            Err(e) => report.errors.push((line, e.desc)),
+        }
+    }
+    Ok(report)
+}
+
+fn check_date(value: &str, name: &str) -> Result<String> {
+    parse_last_touched(value).map_err(|_| format!("invalid {} date '{}'", name, value))?;
+    Ok(value.to_string())
+}
+
+/// the note described by a row, fields that are missing from the row are
+/// left empty
+fn row_to_note(columns: &[Option<Field>], record: &csv::StringRecord) -> Result<Item> {
+    let mut note = Item::default();
+    for (column, value) in columns.iter().zip(record.iter()) {
+        let trimmed = value.trim();
+        match column {
+            Some(Field::Title) => note.title = trimmed.to_string(),
+            Some(Field::Body) => note.body = value.to_string(),
+            Some(Field::Status) if !trimmed.is_empty() => {
+                note.status = extract_status(Some(trimmed.to_string()))
+                                  .map_err(|_| format!("invalid status '{}'", trimmed))?
+                                  .unwrap_or(Status::Blank);
+            }
+            Some(Field::Touched) if !trimmed.is_empty() => {
+                note.last_touched = check_date(trimmed, "last touched")?;
+            }
+            Some(Field::Created) if !trimmed.is_empty() => {
+                note.created = Some(check_date(trimmed, "created")?);
+            }
+            Some(Field::Due) if !trimmed.is_empty() => {
+                note.due = Some(check_date(trimmed, "due")?);
+            }
+            Some(Field::Tags) => {
+                note.tags = trimmed.split(',')
+                                   .map(|t| t.trim().to_string())
+                                   .filter(|t| !t.is_empty())
+                                   .collect();
+            }
+            _ => {}
+        }
+    }
+    if note.title.is_empty() {
+        return Err("missing title".into());
+    }
+    Ok(note)
+}
+
+static HTML_STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; }
+th { border-bottom: 2px solid #999; }
+a { color: #1a5fb4; text-decoration: none; }
+.badge { font-size: 0.8em; padding: 0.1em 0.5em; border-radius: 0.6em; color: #fff; }
+.badge.started { background: #c88800; }
+.badge.urgent { background: #c01c28; }
+.badge.done { background: #5e5c64; }
+.body-marker { color: #888; }
+.meta { color: #666; }
+#search { width: 100%; padding: 0.4em; margin-bottom: 1em; font-size: 1em; }
+pre { background: #f4f4f4; padding: 0.6em; overflow-x: auto; }
+";
+
+static HTML_SEARCH: &str = "var input = document.getElementById('search');
+input.addEventListener('input', function () {
+    var q = input.value.toLowerCase();
+    var shown = {};
+    THECA_INDEX.forEach(function (n) {
+        shown[n.id] = q === '' || n.title.toLowerCase().indexOf(q) !== -1
+            || n.body.toLowerCase().indexOf(q) !== -1;
+    });
+    document.querySelectorAll('tr[data-id]').forEach(function (row) {
+        row.style.display = shown[row.getAttribute('data-id')] ? '' : 'none';
+    });
+});
+";
+
+/// An entry in the client side search index of the html export
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    id: usize,
+    title: &'a str,
+    status: String,
+    body: &'a str,
+    url: String,
+}
+
+/// escape text for use in html content and attribute values
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// link targets outside http, https and mailto become `#`, relative ones
+/// are kept
+fn safe_url(url: CowStr) -> CowStr {
+    // browsers ignore whitespace and control characters inside a scheme
+    let cleaned: String = url.chars().filter(|c| !c.is_ascii_whitespace() && !c.is_control()).collect();
+    let scheme = match cleaned.find([':', '/', '?', '#']) {
+        Some(i) if cleaned[i..].starts_with(':') => cleaned[..i].to_ascii_lowercase(),
+        _ => return url,
+    };
+    match scheme.as_str() {
+        "http" | "https" | "mailto" => url,
+        _ => CowStr::Borrowed("#"),
+    }
+}
+
+/// render a markdown note body to html, raw html in the body is escaped
+/// rather than passed through and unsafe link targets are dropped
+pub fn markdown_to_html(body: &str) -> String {
+    let parser = Parser::new_ext(body, Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS |
+                                       Options::ENABLE_STRIKETHROUGH)
+                     .map(|e| match e {
+                         Event::Html(h) | Event::InlineHtml(h) => Event::Text(h),
+                         Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                             Event::Start(Tag::Link { link_type, dest_url: safe_url(dest_url), title, id })
+                         }
+                         Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+                             Event::Start(Tag::Image { link_type, dest_url: safe_url(dest_url), title, id })
+                         }
+                         e => e,
+                     });
+    let mut out = String::new();
+    html::push_html(&mut out, parser);
+    out
+}
+
+fn note_page_name(note: &Item) -> String {
+    format!("{}.html", note.id)
+}
+
+fn status_badge(note: &Item) -> String {
+    match note.status {
+        Status::Blank => "".to_string(),
+        s => format!("<span class=\"badge {}\">{}</span>", s.name(), s),
+    }
+}
+
+fn html_page(title: &str, css: &str, content: &str) -> String {
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{}</title>\n<link rel=\"stylesheet\" href=\"{}\">\n</head>\n\
+             <body>\n{}</body>\n</html>\n",
+            escape_html(title),
+            css,
+            content)
+}
+
+/// write the notes of a profile as a static site into `dir`: an index
+/// table, one page per note and a client side search index
+pub fn export_html(dir: &Path, profile_name: &str, notes: &[Item], dates: &DateDisplay) -> Result<()> {
+    let notes_dir = dir.join("notes");
+    create_dir_all(&notes_dir)?;
+    File::create(dir.join("style.css"))?.write_all(HTML_STYLE.as_bytes())?;
+
+    // index
+    let mut rows = String::new();
+    for n in notes {
+        rows.push_str(&format!("<tr data-id=\"{}\"><td>{}</td><td><a href=\"notes/{}\">{}</a>{}</td>\
+                                <td>{}</td><td>{}</td></tr>\n",
+                               n.id,
+                               n.id,
+                               note_page_name(n),
+                               escape_html(&n.title),
+                               if n.body.is_empty() {
+                                   ""
+                               } else {
+                                   " <span class=\"body-marker\">(+)</span>"
+                               },
+                               status_badge(n),
+                               dates.format(&n.last_touched)?));
+    }
+    let index = format!("<h1>{}</h1>\n<input id=\"search\" type=\"search\" placeholder=\"search notes\">\n\
+                         <table>\n<thead><tr><th>id</th><th>title</th><th>status</th>\
+                         <th>last touched</th></tr></thead>\n<tbody>\n{}</tbody>\n</table>\n\
+                         <script src=\"search-index.js\"></script>\n<script src=\"search.js\"></script>\n",
+                        escape_html(profile_name),
+                        rows);
+    File::create(dir.join("index.html"))?
+        .write_all(html_page(profile_name, "style.css", &index).as_bytes())?;
+
+    // search index, a script rather than json so it also loads from file://
+    let entries: Vec<SearchEntry> = notes.iter()
+                                         .map(|n| SearchEntry {
+                                             id: n.id,
+                                             title: &n.title,
+                                             status: n.status.to_string(),
+                                             body: &n.body,
+                                             url: format!("notes/{}", note_page_name(n)),
+                                         })
+                                         .collect();
+    let index_js = format!("var THECA_INDEX = {};\n", serde_json::to_string(&entries)?);
+    File::create(dir.join("search-index.js"))?.write_all(index_js.as_bytes())?;
+    File::create(dir.join("search.js"))?.write_all(HTML_SEARCH.as_bytes())?;
+
+    // note pages
+    for n in notes {
+        let page = format!("<p><a href=\"../index.html\">&larr; {}</a></p>\n<h1>{} {}</h1>\n\
+                            <p class=\"meta\">note {} &middot; last touched {}</p>\n{}",
+                           escape_html(profile_name),
+                           escape_html(&n.title),
+                           status_badge(n),
+                           n.id,
+                           dates.format(&n.last_touched)?,
+                           markdown_to_html(&n.body));
+        File::create(notes_dir.join(note_page_name(n)))?
+            .write_all(html_page(&n.title, "../style.css", &page).as_bytes())?;
+    }
+    Ok(())
+}
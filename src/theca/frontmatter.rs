@@ -0,0 +1,97 @@
+// frontmatter.rs
+//   the YAML frontmatter of the markdown files a profile is mirrored to.
+//   every note field is written to the frontmatter, keys theca doesn't know
+//   about are kept so users can add their own.
+
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Deserialize};
+
+use crate::errors::Result;
+use crate::item::{Item, Status};
+use crate::utils::extract_status;
+
+/// The frontmatter of a mirrored note
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+pub struct Frontmatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// kept as text so hand written values like `urgent` are accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_touched: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    /// keys added by the user
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl Frontmatter {
+    /// the frontmatter for a note
+    pub fn from_item(item: &Item) -> Frontmatter {
+        Frontmatter {
+            id: Some(item.id),
+            title: Some(item.title.clone()),
+            status: Some(item.status.name().to_string()),
+            last_touched: Some(item.last_touched.clone()),
+            created: item.created.clone(),
+            due: item.due.clone(),
+            tags: item.tags.clone(),
+            parent: item.parent,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// the status in the frontmatter, a missing or empty status is blank
+    pub fn status(&self) -> Result<Status> {
+        match self.status.as_deref().map(str::trim) {
+            None | Some("") => Ok(Status::Blank),
+            Some(s) => Ok(extract_status(Some(s.to_string()))?.unwrap_or(Status::Blank)),
+        }
+    }
+}
+
+// the frontmatter block and the body of a file, if it starts with one
+fn split(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix("---\n")?;
+    if let Some(body) = rest.strip_prefix("---\n") {
+        return Some(("", body));
+    }
+    let end = rest.find("\n---\n")?;
+    Some((&rest[..end + 1], &rest[end + 5..]))
+}
+
+/// split a markdown file into its frontmatter and body. files without a
+/// frontmatter block, or with one that isn't valid YAML, give `None`.
+pub fn parse(content: &str) -> Option<(Frontmatter, String)> {
+    let (yaml, body) = split(content)?;
+    let frontmatter = if yaml.trim().is_empty() {
+        Frontmatter::default()
+    } else {
+        serde_yaml::from_str(yaml).ok()?
+    };
+    Some((frontmatter, body.to_string()))
+}
+
+/// the body of a markdown file, without its frontmatter block if it has one
+pub fn strip(content: &str) -> String {
+    match split(content) {
+        Some((_, body)) => body.to_string(),
+        None => content.to_string(),
+    }
+}
+
+/// render a markdown file from frontmatter and a body
+pub fn render(frontmatter: &Frontmatter, body: &str) -> Result<String> {
+    Ok(format!("---\n{}---\n{}", serde_yaml::to_string(frontmatter)?, body))
+}
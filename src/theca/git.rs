@@ -0,0 +1,258 @@
+// git.rs
+//   git history for profiles. a profile directory can be a git repository,
+//   profile.yaml and the markdown mirror are committed after every command
+//   that changes the profile and pulls are merged note by note.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::errors::Result;
+use crate::item::Item;
+use crate::profile::Profile;
+use crate::sync::{self, note_hash, Mirror, SyncState};
+use crate::utils::{cmp_last_touched, profile_paths};
+
+/// The outcome of `theca git pull`
+pub enum Pulled {
+    UpToDate,
+    /// the profile on disk was replaced by the remote one
+    FastForward,
+    /// both sides changed, the merged notes still have to be saved and
+    /// committed. `conflicts` describes the fields both sides changed.
+    Merged { notes: Vec<Item>, conflicts: Vec<String> },
+}
+
+// run git in `dir`, its output or an error with what git printed
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = match Command::new("git").arg("-C").arg(dir).args(args).output() {
+        Ok(o) => o,
+        Err(e) => return Err(format!("failed to run git: {}", e).into()),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {} failed: {}", args[0], stderr.trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// whether a git command succeeds, for the ones that answer with their status
+fn git_ok(dir: &Path, args: &[&str]) -> bool {
+    Command::new("git").arg("-C").arg(dir).args(args).output().is_ok_and(|o| o.status.success())
+}
+
+/// whether the profile directory is a git repository of its own
+pub fn is_repo(profile_dir: &Path) -> bool {
+    profile_dir.join(".git").exists()
+}
+
+fn repo_dir(profile_name: &str, profile_folder: &Option<String>) -> Result<PathBuf> {
+    let (profile_dir, _) = profile_paths(profile_name, profile_folder)?;
+    if !is_repo(&profile_dir) {
+        return Err(format!("profile '{}' has no git history, run `theca git init` first", profile_name).into());
+    }
+    Ok(profile_dir)
+}
+
+// the files of a profile that are versioned, relative to its directory.
+// other profiles in the same directory are left out.
+fn tracked_paths(profile_name: &str, profile_folder: &Option<String>) -> Result<Vec<String>> {
+    let (profile_dir, _) = profile_paths(profile_name, profile_folder)?;
+    let mut paths = vec!["profile.yaml".to_string()];
+    let mirror = Mirror::load(profile_name, profile_folder)?;
+    if mirror.enabled {
+        let prefix = mirror.dir.as_deref().map(|d| format!("{}/", d.trim_end_matches('/'))).unwrap_or_default();
+        for file in sync::read_files(&mirror.dir(&profile_dir), &mirror, &SyncState::default())? {
+            paths.push(format!("{}{}", prefix, file.name));
+        }
+    }
+    Ok(paths)
+}
+
+/// commit the profile if its directory is a git repository, nothing is
+/// committed when nothing changed
+pub fn commit(profile_name: &str, profile_folder: &Option<String>, message: &str) -> Result<()> {
+    let (profile_dir, _) = profile_paths(profile_name, profile_folder)?;
+    if !is_repo(&profile_dir) {
+        return Ok(());
+    }
+    let paths = tracked_paths(profile_name, profile_folder)?;
+    let mut add = vec!["add", "-A", "--"];
+    add.extend(paths.iter().map(|p| p.as_str()));
+    git(&profile_dir, &add)?;
+    // files that were committed before and are gone now
+    let removed = git(&profile_dir, &["ls-files", "--deleted"])?;
+    for file in removed.lines().filter(|f| f.ends_with(".md")) {
+        git(&profile_dir, &["rm", "--cached", "--quiet", "--", file])?;
+    }
+    let merging = profile_dir.join(".git").join("MERGE_HEAD").exists();
+    if merging || !git_ok(&profile_dir, &["diff", "--cached", "--quiet"]) {
+        git(&profile_dir, &["commit", "--quiet", "-m", message])?;
+    }
+    Ok(())
+}
+
+/// make the profile directory a git repository and commit the profile, the
+/// repository's directory if it was created
+pub fn init(profile_name: &str, profile_folder: &Option<String>, remote: &Option<String>) -> Result<Option<PathBuf>> {
+    let (profile_dir, _) = profile_paths(profile_name, profile_folder)?;
+    let created = !is_repo(&profile_dir);
+    if created {
+        git(&profile_dir, &["init", "--quiet"])?;
+        // the sync state is about this copy of the files only
+        let ignore = profile_dir.join(".gitignore");
+        if !ignore.exists() {
+            std::fs::write(&ignore, format!("{}\n", sync::SYNC_STATE_FILE))?;
+            git(&profile_dir, &["add", ".gitignore"])?;
+        }
+    }
+    if let Some(url) = remote {
+        if git_ok(&profile_dir, &["remote", "get-url", "origin"]) {
+            git(&profile_dir, &["remote", "set-url", "origin", url])?;
+        } else {
+            git(&profile_dir, &["remote", "add", "origin", url])?;
+        }
+    }
+    commit(profile_name, profile_folder, &format!("add profile {}", profile_name))?;
+    Ok(if created { Some(profile_dir) } else { None })
+}
+
+/// the commits of the profile, newest first
+pub fn log(profile_name: &str, profile_folder: &Option<String>, limit: Option<usize>) -> Result<String> {
+    let dir = repo_dir(profile_name, profile_folder)?;
+    let limit = limit.map(|l| format!("-{}", l));
+    let mut args = vec!["log", "--date=format:%F %T", "--format=%h  %ad  %s"];
+    args.extend(limit.as_deref());
+    args.extend(["--", "profile.yaml"]);
+    git(&dir, &args)
+}
+
+/// push the profile to the `origin` remote
+pub fn push(profile_name: &str, profile_folder: &Option<String>) -> Result<()> {
+    let dir = repo_dir(profile_name, profile_folder)?;
+    git(&dir, &["push", "--quiet", "-u", "origin", "HEAD"])?;
+    Ok(())
+}
+
+// the notes of profile.yaml at a commit, none if it didn't exist then
+fn notes_at(dir: &Path, rev: &str, key: Option<&String>, encrypted: bool) -> Result<Vec<Item>> {
+    let spec = format!("{}:./profile.yaml", rev);
+    if !git_ok(dir, &["cat-file", "-e", &spec]) {
+        return Ok(vec![]);
+    }
+    let output = match Command::new("git").arg("-C").arg(dir).args(["show", &spec]).output() {
+        Ok(o) => o.stdout,
+        Err(e) => return Err(format!("failed to run git: {}", e).into()),
+    };
+    Ok(Profile::from_bytes(output, key, encrypted, Path::new(&spec))?.notes)
+}
+
+/// fetch `origin` and bring in its changes. when both sides have new
+/// commits the notes are merged by id and the merge is concluded by the
+/// next commit.
+pub fn pull(profile_name: &str,
+            profile_folder: &Option<String>,
+            profile: &Profile,
+            key: Option<&String>)
+            -> Result<Pulled> {
+    let dir = repo_dir(profile_name, profile_folder)?;
+    commit(profile_name, profile_folder, "save local changes")?;
+    git(&dir, &["fetch", "--quiet", "origin"])?;
+    let branch = git(&dir, &["rev-parse", "--abbrev-ref", "HEAD"])?.trim().to_string();
+    let theirs = format!("origin/{}", branch);
+    if !git_ok(&dir, &["rev-parse", "--verify", "--quiet", &theirs]) ||
+       git_ok(&dir, &["merge-base", "--is-ancestor", &theirs, "HEAD"]) {
+        return Ok(Pulled::UpToDate);
+    }
+    if git_ok(&dir, &["merge-base", "--is-ancestor", "HEAD", &theirs]) {
+        git(&dir, &["merge", "--quiet", "--ff-only", &theirs])?;
+        return Ok(Pulled::FastForward);
+    }
+
+    let base = git(&dir, &["merge-base", "HEAD", &theirs])?.trim().to_string();
+    let base_notes = notes_at(&dir, &base, key, profile.encrypted)?;
+    let their_notes = notes_at(&dir, &theirs, key, profile.encrypted)?;
+    let (notes, conflicts) = merge_notes(&base_notes, &profile.notes, &their_notes);
+    // record the merge, the files are written when the merged profile is saved
+    git(&dir, &["merge", "--quiet", "--no-commit", "--no-ff", "-s", "ours", &theirs])?;
+    Ok(Pulled::Merged { notes, conflicts })
+}
+
+/// merge two versions of a profile's notes by id, `base` is the version both
+/// started from. a field changed on both sides keeps the newer change. the
+/// merged notes come with a description of every conflict.
+pub fn merge_notes(base: &[Item], ours: &[Item], theirs: &[Item]) -> (Vec<Item>, Vec<String>) {
+    let find = |notes: &[Item], id: usize| notes.iter().find(|n| n.id == id).cloned();
+    let same = |a: &Item, b: &Item| note_hash(a) == note_hash(b);
+    let ids: BTreeSet<usize> = base.iter().chain(ours).chain(theirs).map(|n| n.id).collect();
+
+    let mut merged = vec![];
+    let mut added_twice = vec![];
+    let mut conflicts = vec![];
+    for id in ids {
+        match (find(base, id), find(ours, id), find(theirs, id)) {
+            (None, Some(o), Some(t)) if !same(&o, &t) => {
+                merged.push(o);
+                added_twice.push(t);
+            }
+            (_, Some(o), Some(t)) if same(&o, &t) => merged.push(o),
+            (Some(b), Some(o), Some(t)) => {
+                let (n, fields) = merge_note(&b, &o, &t);
+                if !fields.is_empty() {
+                    conflicts.push(format!("note {}: {} changed on both sides, kept the newer change",
+                                           id,
+                                           fields.join(", ")));
+                }
+                merged.push(n);
+            }
+            (None, Some(n), None) | (None, None, Some(n)) => merged.push(n),
+            // deleted on one side and changed on the other, unchanged
+            // notes stay deleted
+            (Some(b), Some(n), None) | (Some(b), None, Some(n)) if !same(&b, &n) => {
+                conflicts.push(format!("note {} was deleted on one side and changed on the other, kept it", id));
+                merged.push(n);
+            }
+            _ => {}
+        }
+    }
+    // notes both sides added with the same id, the remote one gets a new id
+    for mut n in added_twice {
+        let old = n.id;
+        n.id = merged.iter().map(|m| m.id).max().unwrap_or(0) + 1;
+        conflicts.push(format!("note {} was added on both sides, the remote one is now note {}", old, n.id));
+        merged.push(n);
+    }
+    merged.sort_by_key(|n| n.id);
+    (merged, conflicts)
+}
+
+// three way merge of a note field by field, with the fields both sides
+// changed
+fn merge_note(base: &Item, ours: &Item, theirs: &Item) -> (Item, Vec<&'static str>) {
+    let theirs_newer = cmp_last_touched(&theirs.last_touched, &ours.last_touched)
+                           .is_ok_and(|o| o == std::cmp::Ordering::Greater);
+    let mut merged = ours.clone();
+    let mut conflicts = vec![];
+    macro_rules! merge_field {
+        ($field:ident, $name:expr) => {
+            if ours.$field == base.$field {
+                merged.$field = theirs.$field.clone();
+            } else if theirs.$field != base.$field && theirs.$field != ours.$field {
+                conflicts.push($name);
+                if theirs_newer {
+                    merged.$field = theirs.$field.clone();
+                }
+            }
+        };
+    }
+    merge_field!(title, "title");
+    merge_field!(status, "status");
+    merge_field!(body, "body");
+    merge_field!(due, "due");
+    merge_field!(tags, "tags");
+    merge_field!(parent, "parent");
+    if theirs_newer {
+        merged.last_touched = theirs.last_touched.clone();
+    }
+    (merged, conflicts)
+}
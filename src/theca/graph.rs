@@ -0,0 +1,163 @@
+// graph.rs
+//   `theca graph`, the notes of a profile and how they relate as a Graphviz
+//   dot graph or a Mermaid flowchart. notes are connected by the links in
+//   their bodies and to their parents, notes sharing a tag are connected
+//   through a node for the tag.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::errors::Result;
+use crate::item::{Item, Status};
+use crate::links;
+
+/// Formats a graph can be written in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    /// Graphviz, e.g. `theca graph | dot -Tsvg > notes.svg`
+    Dot,
+    /// a Mermaid flowchart, for markdown that renders them
+    Mermaid,
+}
+
+/// How two nodes of the graph are related
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edge {
+    /// the body of `from` links to `to`
+    Link { from: usize, to: usize },
+    /// `child` is part of `parent`
+    Parent { child: usize, parent: usize },
+    /// the note has the tag, an index into the shared tags
+    Tag { note: usize, tag: usize },
+}
+
+/// The notes of a graph and what connects them
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    pub notes: Vec<Item>,
+    /// tags at least two of the notes have
+    pub tags: Vec<String>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// the graph of `notes`, links are looked up among `all` notes but only
+    /// those between the notes of the graph are edges
+    pub fn new(notes: &[Item], all: &[Item]) -> Graph {
+        let included = |id: usize| notes.iter().any(|n| n.id == id);
+        let mut edges = vec![];
+        for note in notes {
+            let mut linked = vec![];
+            for to in links::outgoing(note, all).into_iter().filter_map(|l| l.to) {
+                if to != note.id && included(to) && !linked.contains(&to) {
+                    edges.push(Edge::Link { from: note.id, to });
+                    linked.push(to);
+                }
+            }
+            if let Some(parent) = note.parent.filter(|p| included(*p)) {
+                edges.push(Edge::Parent { child: note.id, parent });
+            }
+        }
+
+        let mut tagged: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for note in notes {
+            for tag in note.tags.iter() {
+                tagged.entry(tag.as_str()).or_default().push(note.id);
+            }
+        }
+        let mut tags = vec![];
+        for (tag, ids) in tagged.into_iter().filter(|(_, ids)| ids.len() > 1) {
+            edges.extend(ids.into_iter().map(|note| Edge::Tag { note, tag: tags.len() }));
+            tags.push(tag.to_string());
+        }
+        Graph { notes: notes.to_vec(), tags, edges }
+    }
+
+    /// the graph in `format`, `name` is the name of the profile
+    pub fn render(&self, format: GraphFormat, name: &str) -> Result<String> {
+        match format {
+            GraphFormat::Dot => self.dot(name),
+            GraphFormat::Mermaid => self.mermaid(),
+        }
+    }
+
+    fn dot(&self, name: &str) -> Result<String> {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::new();
+        writeln!(out, "digraph {} {{", quote(name))?;
+        writeln!(out, "    node [shape=box, style=\"rounded,filled\", fillcolor=\"{}\"];", color(Status::Blank))?;
+        for note in self.notes.iter() {
+            let label = match note.status {
+                Status::Blank => format!("{}: {}", note.id, note.title),
+                status => format!("{}: {}\n{}", note.id, note.title, status),
+            };
+            let label = quote(&label).replace('\n', "\\n");
+            match note.status {
+                Status::Blank => writeln!(out, "    n{} [label={}];", note.id, label)?,
+                status => writeln!(out, "    n{} [label={}, fillcolor=\"{}\"];", note.id, label, color(status))?,
+            }
+        }
+        for (i, tag) in self.tags.iter().enumerate() {
+            writeln!(out, "    t{} [label={}, shape=ellipse, style=dashed];", i, quote(&format!("#{}", tag)))?;
+        }
+        for edge in self.edges.iter() {
+            match *edge {
+                Edge::Link { from, to } => writeln!(out, "    n{} -> n{};", from, to)?,
+                Edge::Parent { child, parent } => {
+                    writeln!(out, "    n{} -> n{} [style=bold, label=\"part of\"];", child, parent)?
+                }
+                Edge::Tag { note, tag } => writeln!(out, "    n{} -> t{} [style=dotted, arrowhead=none];", note, tag)?,
+            }
+        }
+        writeln!(out, "}}")?;
+        Ok(out)
+    }
+
+    fn mermaid(&self) -> Result<String> {
+        // mermaid has entities instead of escapes
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+        let mut out = String::new();
+        writeln!(out, "flowchart LR")?;
+        for note in self.notes.iter() {
+            let label = match note.status {
+                Status::Blank => format!("{}: {}", note.id, note.title),
+                status => format!("{}: {}<br/>{}", note.id, note.title, status),
+            };
+            writeln!(out, "    n{}[{}]", note.id, quote(&label))?;
+        }
+        for (i, tag) in self.tags.iter().enumerate() {
+            writeln!(out, "    t{}([{}])", i, quote(&format!("#{}", tag)))?;
+        }
+        for edge in self.edges.iter() {
+            match *edge {
+                Edge::Link { from, to } => writeln!(out, "    n{} --> n{}", from, to)?,
+                Edge::Parent { child, parent } => writeln!(out, "    n{} ==>|part of| n{}", child, parent)?,
+                Edge::Tag { note, tag } => writeln!(out, "    n{} -.- t{}", note, tag)?,
+            }
+        }
+        for status in [Status::Started, Status::Urgent, Status::Done] {
+            let ids: Vec<String> = self.notes
+                                       .iter()
+                                       .filter(|n| n.status == status)
+                                       .map(|n| format!("n{}", n.id))
+                                       .collect();
+            if !ids.is_empty() {
+                writeln!(out, "    classDef {} fill:{}", status.name(), color(status))?;
+                writeln!(out, "    class {} {}", ids.join(","), status.name())?;
+            }
+        }
+        Ok(out)
+    }
+}
+
+// the fill color of the notes with a status
+fn color(status: Status) -> &'static str {
+    match status {
+        Status::Blank => "#ffffff",
+        Status::Started => "#fff3b0",
+        Status::Urgent => "#ffb3b3",
+        Status::Done => "#c8e6c9",
+    }
+}
@@ -1,23 +1,96 @@
+use std::cmp::Ordering;
 use std::fmt;
-use std::iter::repeat;
 use std::io::{self, Write};
+use std::str::FromStr;
 
+use crate::checklist;
 use crate::lineformat::LineFormat;
-use crate::utils::{format_field, localize_last_touched_string};
-use crate::errors::Result;
+use crate::theme::{paint, style_ansi};
+use crate::utils::{cmp_last_touched, format_field, normalize_timestamp, DateDisplay};
+use crate::errors::{Error, Result};
+use regex::Regex;
 use serde::{Serialize, Deserialize};
 
 /// Represents a note within a profile
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Item {
     pub id: usize,
     pub title: String,
     pub status: Status,
     pub body: String,
     pub last_touched: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// the note this one is a part of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
 }
 
 impl Item {
+    /// the value of a single field as plain text
+    pub fn field(&self, field: Field) -> String {
+        match field {
+            Field::Id => self.id.to_string(),
+            Field::Title => self.title.clone(),
+            Field::Status => format!("{}", self.status),
+            Field::Body => self.body.clone(),
+            Field::Touched => self.last_touched.clone(),
+            Field::Created => self.created.clone().unwrap_or_default(),
+            Field::Due => self.due.clone().unwrap_or_default(),
+            Field::Tags => self.tags.join(","),
+            Field::Progress => checklist::progress(&self.body).map(|(c, t)| format!("{}/{}", c, t)).unwrap_or_default(),
+        }
+    }
+
+    /// the value of a field as shown in list output
+    pub fn display_field(&self, field: Field, dates: &DateDisplay) -> Result<String> {
+        match field {
+            Field::Touched | Field::Created | Field::Due => {
+                let value = self.field(field);
+                if value.is_empty() {
+                    Ok(value)
+                } else {
+                    dates.format(&value)
+                }
+            }
+            f => Ok(self.field(f)),
+        }
+    }
+
+    /// add and remove tags, each tag is kept once
+    pub fn retag(&mut self, add: &[String], remove: &[String]) {
+        for tag in add {
+            if !self.tags.contains(tag) {
+                self.tags.push(tag.clone());
+            }
+        }
+        self.tags.retain(|t| !remove.contains(t));
+    }
+
+    /// rewrite all timestamps in UTC
+    pub fn normalize_dates(&mut self) {
+        self.last_touched = normalize_timestamp(&self.last_touched);
+        for d in [&mut self.created, &mut self.due].into_iter().flatten() {
+            *d = normalize_timestamp(d);
+        }
+    }
+
+    /// a copy of the note with all timestamps as RFC 3339, for machine
+    /// readable output
+    pub fn with_rfc3339_dates(&self, dates: &DateDisplay) -> Item {
+        let convert = |d: &String| dates.rfc3339(d).unwrap_or_else(|_| d.clone());
+        Item {
+            last_touched: convert(&self.last_touched),
+            created: self.created.as_ref().map(convert),
+            due: self.due.as_ref().map(convert),
+            ..self.clone()
+        }
+    }
+
     /// print a note as a line
     pub fn print(&self, line_format: &LineFormat, search_body: bool) -> Result<()> {
         self.write(&mut io::stdout(), line_format, search_body)
@@ -28,37 +101,38 @@ impl Item {
                            line_format: &LineFormat,
                            search_body: bool)
                            -> Result<()> {
-        let column_seperator: String = repeat(' ')
-                                           .take(line_format.colsep)
-                                           .collect();
-        write!(output,
-                    "{}",
-                    format_field(&self.id.to_string(), line_format.id_width, false))?;
-        write!(output, "{}", column_seperator)?;
-        if !self.body.is_empty() && !search_body {
-            write!(output,
-                        "{}",
-                        format_field(&self.title, if line_format.title_width > 4 { line_format.title_width - 4 } else { 0 }, true))?;
-            write!(output, "{}", format_field(&" (+)".to_string(), 4, false))?;
+        let column_seperator: String = " ".repeat(line_format.colsep);
+        // styles are applied after padding so they don't count towards widths,
+        // highlighted parts go back to the row style afterwards
+        let (row, id, marker) = match line_format.colors {
+            Some(ref theme) => (style_ansi(theme.status(self.status))?, theme.id.as_str(), theme.marker.as_str()),
+            None => (String::new(), "", ""),
+        };
+        let highlight = |text: &str, style: &str| -> Result<String> {
+            Ok(format!("{}{}", paint(text, style)?, if style.trim().is_empty() { "" } else { &row }))
+        };
+        // children are indented under their parent in tree listings
+        let title = format!("{}{}", "  ".repeat(line_format.depth(self.id)), self.title);
+        let mut columns = vec![];
+        for c in line_format.visible_columns() {
+            let width = line_format.width(c);
+            columns.push(match c {
+                Field::Id => highlight(&format_field(&self.id.to_string(), width, false), id)?,
+                Field::Title if !self.body.is_empty() && !search_body => {
+                    format!("{}{}",
+                            format_field(&title, width.saturating_sub(4), true),
+                            highlight(&format_field(" (+)", 4, false), marker)?)
+                }
+                Field::Title => format_field(&title, width, true),
+                Field::Tags => format_field(&self.display_field(c, &line_format.dates)?, width, true),
+                _ => format_field(&self.display_field(c, &line_format.dates)?, width, false),
+            });
+        }
+        if row.is_empty() {
+            writeln!(output, "{}", columns.join(&column_seperator))?;
         } else {
-            write!(output,
-                        "{}",
-                        format_field(&self.title, line_format.title_width, true))?;
-        }
-        write!(output, "{}", column_seperator)?;
-        if line_format.status_width != 0 {
-            write!(output,
-                        "{}",
-                        format_field(&format!("{}", self.status),
-                                     line_format.status_width,
-                                     false))?;
-            write!(output, "{}", column_seperator)?;
-        }
-        writeln!(output,
-                      "{}",
-                      format_field(&localize_last_touched_string(&*self.last_touched)?,
-                                   line_format.touched_width,
-                                   false))?;
+            writeln!(output, "{}{}{}", row, columns.join(&column_seperator), style_ansi("reset")?)?;
+        }
         if search_body {
             for l in self.body.lines() {
                 writeln!(output, "\t{}", l)?;
@@ -68,14 +142,37 @@ impl Item {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
 pub enum Status {
+    #[default]
     Blank,
     Started,
     Urgent,
     Done,
 }
 
+impl Status {
+    /// lowercase name as accepted by `--status`
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Status::Blank => "blank",
+            Status::Started => "started",
+            Status::Urgent => "urgent",
+            Status::Done => "done",
+        }
+    }
+
+    /// sort priority, lower comes first: Urgent > Started > Blank > Done
+    pub fn priority(&self) -> u8 {
+        match *self {
+            Status::Urgent => 0,
+            Status::Started => 1,
+            Status::Blank => 2,
+            Status::Done => 3,
+        }
+    }
+}
+
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f,
@@ -88,3 +185,258 @@ impl fmt::Display for Status {
                })
     }
 }
+
+/// A named note field, used to pick columns for list output, export and
+/// import and as a sort key
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Field {
+    Id,
+    Title,
+    Status,
+    Body,
+    Touched,
+    Created,
+    Due,
+    Tags,
+    /// checked and total checklist items, e.g. 3/7
+    Progress,
+}
+
+impl Field {
+    /// the stored fields in their default export order, everything import
+    /// reads back
+    pub fn all() -> Vec<Field> {
+        vec![Field::Id, Field::Title, Field::Status, Field::Body, Field::Touched, Field::Created, Field::Due,
+             Field::Tags]
+    }
+
+    /// the default columns of list output
+    pub fn list_default() -> Vec<Field> {
+        vec![Field::Id, Field::Title, Field::Status, Field::Progress, Field::Touched]
+    }
+
+    /// parse a comma separated list of field names
+    pub fn parse_list(s: &str) -> Result<Vec<Field>> {
+        s.split(',').map(|f| f.trim().parse()).collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Field::Id => "id",
+            Field::Title => "title",
+            Field::Status => "status",
+            Field::Body => "body",
+            Field::Touched => "touched",
+            Field::Created => "created",
+            Field::Due => "due",
+            Field::Tags => "tags",
+            Field::Progress => "progress",
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Field> {
+        match s.to_lowercase().as_str() {
+            "id" => Ok(Field::Id),
+            "title" => Ok(Field::Title),
+            "status" => Ok(Field::Status),
+            "body" => Ok(Field::Body),
+            "touched" | "last_touched" | "last touched" => Ok(Field::Touched),
+            "created" => Ok(Field::Created),
+            "due" => Ok(Field::Due),
+            "tags" => Ok(Field::Tags),
+            "progress" => Ok(Field::Progress),
+            _ => {
+                Err(format!("unknown field '{}' (id, title, status, body, touched, created, due, \
+                             tags, progress)",
+                            s)
+                        .into())
+            }
+        }
+    }
+}
+
+/// the tags given on the command line, trimmed. tags are comma separated in
+/// exports so they can't hold commas.
+pub fn check_tags(tags: &[String]) -> Result<Vec<String>> {
+    let mut checked = vec![];
+    for tag in tags.iter().map(|t| t.trim()) {
+        if tag.is_empty() || tag.contains(',') {
+            return Err(format!("invalid tag '{}', tags can't be empty or hold commas", tag).into());
+        }
+        checked.push(tag.to_string());
+    }
+    Ok(checked)
+}
+
+/// A sort key for list output, `-field` sorts in descending order
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SortKey {
+    pub field: Field,
+    pub descending: bool,
+}
+
+impl SortKey {
+    /// parse a comma separated list of sort keys, e.g. `status,-touched,title`
+    pub fn parse_list(s: &str) -> Result<Vec<SortKey>> {
+        s.split(',').map(|k| k.trim().parse()).collect()
+    }
+
+    /// compare two notes by this key, notes with an empty created, due or
+    /// tags field always sort last
+    pub fn compare(&self, a: &Item, b: &Item) -> Ordering {
+        let ordering = match self.field {
+            Field::Id => a.id.cmp(&b.id),
+            Field::Status => a.status.priority().cmp(&b.status.priority()),
+            Field::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            Field::Body => a.body.cmp(&b.body),
+            Field::Touched => cmp_dates(&a.last_touched, &b.last_touched),
+            // by the share of checked items
+            Field::Progress => match (checklist::progress(&a.body), checklist::progress(&b.body)) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Greater,
+                (Some(_), None) => return Ordering::Less,
+                (Some((ac, at)), Some((bc, bt))) => (ac * bt).cmp(&(bc * at)),
+            },
+            Field::Created | Field::Due | Field::Tags => {
+                let (a, b) = (a.field(self.field), b.field(self.field));
+                match (a.is_empty(), b.is_empty()) {
+                    (true, true) => return Ordering::Equal,
+                    (true, false) => return Ordering::Greater,
+                    (false, true) => return Ordering::Less,
+                    _ if self.field == Field::Tags => a.cmp(&b),
+                    _ => cmp_dates(&a, &b),
+                }
+            }
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+fn cmp_dates(a: &str, b: &str) -> Ordering {
+    cmp_last_touched(a, b).unwrap_or_else(|_| a.cmp(b))
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SortKey> {
+        match s.strip_prefix('-') {
+            Some(f) => Ok(SortKey { field: f.parse()?, descending: true }),
+            None => Ok(SortKey { field: s.strip_prefix('+').unwrap_or(s).parse()?, descending: false }),
+        }
+    }
+}
+
+/// A change to part of a note's body
+#[derive(Clone, Debug)]
+pub enum BodyEdit {
+    Append(String),
+    Prepend(String),
+    /// a sed style substitution of a regex
+    Substitute { pattern: Regex, replacement: String, global: bool },
+    /// replace a line, counting from 1
+    ReplaceLine(usize, String),
+}
+
+impl BodyEdit {
+    /// parse a sed style substitution like `s/old/new/`, with the flags `g`
+    /// to replace every match and `i` to ignore case. any character can
+    /// stand in for `/` and `\1` or `&` in `new` refer to the match.
+    pub fn substitution(expr: &str) -> Result<BodyEdit> {
+        let invalid = || -> Result<BodyEdit> { Err(format!("'{}' isn't a substitution like s/old/new/", expr).into()) };
+        let mut chars = expr.chars();
+        let delimiter = match (chars.next(), chars.next()) {
+            (Some('s'), Some(d)) if !d.is_alphanumeric() && d != '\\' => d,
+            _ => return invalid(),
+        };
+        // split on the delimiter, `\` followed by it stands for the character
+        let mut parts = vec![String::new()];
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(n) if n == delimiter => parts.last_mut().unwrap().push(n),
+                    Some(n) => parts.last_mut().unwrap().extend(['\\', n]),
+                    None => parts.last_mut().unwrap().push('\\'),
+                },
+                c if c == delimiter => parts.push(String::new()),
+                c => parts.last_mut().unwrap().push(c),
+            }
+        }
+        if parts.len() != 3 || parts[0].is_empty() {
+            return invalid();
+        }
+        let flags = &parts[2];
+        if let Some(f) = flags.chars().find(|f| !"gi".contains(*f)) {
+            return Err(format!("unknown substitution flag '{}' (g, i)", f).into());
+        }
+        let pattern = regex::RegexBuilder::new(&parts[0]).case_insensitive(flags.contains('i'))
+                                                         .build()
+                                                         .map_err(|e| format!("invalid pattern: {}", e))?;
+        Ok(BodyEdit::Substitute {
+            pattern,
+            replacement: sed_replacement(&parts[1]),
+            global: flags.contains('g'),
+        })
+    }
+
+    /// the body with the edit made, substituting without a match and lines
+    /// past the end are errors
+    pub fn apply(&self, body: &str) -> Result<String> {
+        match self {
+            BodyEdit::Append(text) if body.is_empty() => Ok(text.clone()),
+            BodyEdit::Append(text) if body.ends_with('\n') => Ok(format!("{}{}", body, text)),
+            BodyEdit::Append(text) => Ok(format!("{}\n{}", body, text)),
+            BodyEdit::Prepend(text) if body.is_empty() => Ok(text.clone()),
+            BodyEdit::Prepend(text) if text.ends_with('\n') => Ok(format!("{}{}", text, body)),
+            BodyEdit::Prepend(text) => Ok(format!("{}\n{}", text, body)),
+            BodyEdit::Substitute { pattern, replacement, global } => {
+                if !pattern.is_match(body) {
+                    return Err(format!("'{}' doesn't match anything", pattern).into());
+                }
+                let limit = if *global { 0 } else { 1 };
+                Ok(pattern.replacen(body, limit, replacement.as_str()).to_string())
+            }
+            BodyEdit::ReplaceLine(n, text) => {
+                let mut lines: Vec<&str> = body.split('\n').collect();
+                // a final newline doesn't start another line
+                let count = lines.len() - usize::from(body.ends_with('\n') || body.is_empty());
+                if *n == 0 || *n > count {
+                    return Err(format!("there is no line {}, the body has {} lines", n, count).into());
+                }
+                lines[n - 1] = text;
+                Ok(lines.join("\n"))
+            }
+        }
+    }
+}
+
+// a sed replacement in the syntax of the regex crate: `\1` and `&` refer to
+// the match, `$` is literal
+fn sed_replacement(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '$' => out.push_str("$$"),
+            '&' => out.push_str("${0}"),
+            '\\' => match chars.next() {
+                Some(d) if d.is_ascii_digit() => out.push_str(&format!("${{{}}}", d)),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('$') => out.push_str("$$"),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
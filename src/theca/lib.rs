@@ -1,18 +1,75 @@
 pub mod args;
+pub mod batch;
+pub mod checklist;
+pub mod config;
 pub mod crypt;
 pub mod errors;
+pub mod export;
+pub mod frontmatter;
+pub mod git;
+pub mod graph;
 pub mod item;
 pub mod lineformat;
+pub mod links;
+pub mod markdown;
+pub mod notetemplate;
+pub mod output;
 pub mod profile;
+pub mod shell;
+pub mod sync;
+pub mod template;
+pub mod theme;
+pub mod tui;
 pub mod utils;
 
+use std::fs::File;
+use std::io::{stdin, Write};
+use std::path::Path;
+
 use clap::Parser;
-use args::{Cli, Commands};
-use profile::{Profile, ProfileFlags};
+use args::{ChildAction, Cli, Commands, GitCommands, ViewArgs, ViewCommands};
+use config::{parse_option, Config, View};
+use utils::{DateDisplay, DisplayTz};
+pub use export::{ExportFormat, ImportFormat};
+use item::{BodyEdit, Field, SortKey};
+use notetemplate::{NoteTemplate, Placeholders};
+use output::OutputFormat;
+pub use profile::{Profile, ProfileFlags};
 use errors::Result;
 
+/// the loaded profile commands run against
+pub struct Session {
+    pub profile: Profile,
+    /// the profile file's fingerprint when it was last read or written
+    pub fingerprint: u64,
+    /// write the profile after every command that changes it, the shell can
+    /// hold changes until `save` instead
+    pub autosave: bool,
+    /// commit messages of the changes that weren't written yet
+    pub unsaved: Vec<String>,
+}
+
+impl Session {
+    /// write the profile and commit the changes made since the last save
+    pub fn save(&mut self, cli: &Cli) -> Result<()> {
+        self.profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &self.fingerprint)?;
+        let (_, path) = utils::profile_paths(&cli.profile, &cli.profile_folder)?;
+        self.fingerprint = utils::profile_fingerprint(path)?;
+        if !self.unsaved.is_empty() {
+            git::commit(&cli.profile, &cli.profile_folder, &self.unsaved.join(", "))?;
+            self.unsaved.clear();
+        }
+        Ok(())
+    }
+}
+
 pub fn r#run() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    // the shell and batch ask for the key once and keep it for every command
+    if matches!(cli.command, Some(Commands::Shell { .. }) | Some(Commands::Batch { .. })) &&
+       cli.encrypted && cli.key.is_none() {
+        cli.key = Some(utils::get_password()?);
+    }
 
     // Determine the profile to load.
     // If command is NewProfile, we still load "default" or whatever --profile says?
@@ -60,7 +117,7 @@ pub fn r#run() -> Result<()> {
     // Then later we create "foo".
     // Use `false` for `new_profile` when running `NewProfile` command, to avoid forcing creation of the *current* profile?
     
-    let (mut profile, fingerprint) = if !is_new_profile_cmd {
+    let (profile, fingerprint) = if !is_new_profile_cmd {
          Profile::new(
             &cli.profile,
             &cli.profile_folder,
@@ -115,22 +172,118 @@ pub fn r#run() -> Result<()> {
         )?
     };
 
-    match &cli.command {
-        Some(Commands::Add { title, body, status, editor }) => {
-            profile.add_note(title, 
-                             &[body.clone()], 
-                             utils::extract_status(status.clone())?, 
-                             false, 
-                             *editor,
-                             true)?;
-             profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &fingerprint)?;
+    // a body piped in is read as if it was given as `-`, other input that
+    // isn't a terminal could block forever and is left alone
+    if utils::is_piped(utils::STDIN_FILENO) {
+        match cli.command {
+            Some(Commands::Add { ref mut body, editor: false, template: None, .. }) if body.is_empty() => *body = "-".to_string(),
+            Some(Commands::Edit { ref mut input, title: None, body: None, status: None, editor: false, due: None,
+                                  ref tags, ref untags, .. }) if tags.is_empty() && untags.is_empty() => {
+                *input = Some("-".to_string())
+            }
+            _ => {}
+        }
+    }
 
+    let mut session = Session { profile, fingerprint, autosave: true, unsaved: vec![] };
+    if let Some(Commands::Shell { autosave }) = cli.command {
+        session.autosave = autosave;
+        return shell::run(&cli, session);
+    }
+    if let Some(Commands::Batch { ref path, atomic }) = cli.command {
+        session.autosave = false;
+        return batch::run(&cli, session, path, atomic);
+    }
+    let out = execute(&cli, &mut session)?;
+    utils::page_output(&out, !cli.no_pager)?;
+
+    Ok(())
+}
+
+/// run the command `cli` stands for against a loaded profile, what it prints
+/// to stdout is returned so it can be paged
+pub fn execute(cli: &Cli, session: &mut Session) -> Result<Vec<u8>> {
+    let config = Config::merged(&cli.profile, &cli.profile_folder)?;
+    let dates = DateDisplay {
+        tz: match cli.tz {
+            Some(ref tz) => tz.parse()?,
+            None => DisplayTz::Local,
+        },
+        relative: cli.relative,
+    };
+    let color = cli.color.enabled();
+    let theme = config.theme.clone().unwrap_or_default();
+    theme.validate()?;
+    // output is collected first so it can be sent through a pager
+    let mut out: Vec<u8> = vec![];
+    let profile = &mut session.profile;
+    // whether the command changed the profile and it has to be written
+    let mut changed = false;
+    // an error to return once what did work is saved
+    let mut failed = None;
+
+    match &cli.command {
+        Some(Commands::Add { title, body, status, editor, parent, due, tags, template: None, .. }) => {
+            if let Some(p) = parent {
+                profile.get_note(*p)?;
+            }
+            let due = due.as_deref().map(utils::parse_due).transpose()?.flatten();
+            let tags = item::check_tags(tags)?;
+            let new_id = profile.add_note(title, 
+                                          std::slice::from_ref(body), 
+                                          utils::extract_status(status.clone())?, 
+                                          body == "-", 
+                                          *editor)?;
+            if let Some(n) = profile.notes.last_mut() {
+                n.parent = *parent;
+                n.due = due;
+                n.retag(&tags, &[]);
+            }
+            writeln!(out, "note {} added", new_id)?;
+            changed = true;
         }
-        Some(Commands::Edit { id, title, body, status, editor }) => {
+        Some(Commands::Add { title, status, parent, due, tags, template: Some(name), vars, .. }) => {
+            if let Some(p) = parent {
+                profile.get_note(*p)?;
+            }
+            let due = due.as_deref().map(utils::parse_due).transpose()?.flatten();
+            let tags = item::check_tags(tags)?;
+            let folder = utils::find_profile_folder(&cli.profile_folder)?;
+            let template = NoteTemplate::load(&folder, name)?;
+            let values = Placeholders {
+                title: title.clone(),
+                profile: cli.profile.clone(),
+                dates,
+                vars: notetemplate::ask_variables(&template.variables()?, vars)?,
+            };
+            let note = template.fill(&values)?;
+            // the filled in body is a start, it is written in the editor
+            let body = if utils::istty(utils::STDOUT_FILENO) && utils::istty(utils::STDIN_FILENO) {
+                let next_id = profile.notes.last().map_or(1, |n| n.id + 1);
+                utils::drop_to_editor(&note.body, Some(next_id), Some(&note.title))?
+            } else {
+                note.body
+            };
+            let status = match utils::extract_status(status.clone())? {
+                Some(s) => Some(s),
+                None => note.status,
+            };
+            let new_id = profile.add_note(&note.title, &[body], status, false, false)?;
+            if let Some(n) = profile.notes.last_mut() {
+                n.tags = note.tags;
+                n.parent = *parent;
+                n.due = due;
+                n.retag(&tags, &[]);
+            }
+            writeln!(out, "note {} added", new_id)?;
+            changed = true;
+        }
+        Some(Commands::Edit { id, input, title, body, status, editor, append, due, tags, untags }) => {
              let flags = ProfileFlags {
                 editor: *editor,
                 encrypted: cli.encrypted,
                 yes: cli.yes,
+                append: *append,
                 ..Default::default()
             };
             // Map Some(String) -> Option<Status>
@@ -140,24 +293,102 @@ pub fn r#run() -> Result<()> {
                 None
             };
             
-            profile.edit_note(*id, title, body, &st, false, flags)?;
-            profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &fingerprint)?;
+            let use_stdin = input.is_some() || body.as_deref() == Some("-");
+            let body = if use_stdin { &None } else { body };
+            let due = due.as_deref().map(utils::parse_due).transpose()?;
+            let (tags, untags) = (item::check_tags(tags)?, item::check_tags(untags)?);
+            profile.edit_note(*id, title, body, &st, use_stdin, flags)?;
+            if let Some(n) = profile.notes.iter_mut().find(|n| n.id == *id) {
+                if let Some(due) = due {
+                    n.due = due;
+                }
+                n.retag(&tags, &untags);
+            }
+            writeln!(out, "edited note {}", id)?;
+            changed = true;
 
         }
-        Some(Commands::Del { id }) => {
-            profile.delete_note(id);
-            profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &fingerprint)?;
+        Some(Commands::Append { id, text }) => {
+            profile.edit_body(*id, &BodyEdit::Append(utils::text_or_stdin(text)?))?;
+            writeln!(out, "edited note {}", id)?;
+            changed = true;
+        }
+        Some(Commands::Prepend { id, text }) => {
+            profile.edit_body(*id, &BodyEdit::Prepend(utils::text_or_stdin(text)?))?;
+            writeln!(out, "edited note {}", id)?;
+            changed = true;
+        }
+        Some(Commands::Sed { id, expr }) => {
+            profile.edit_body(*id, &BodyEdit::substitution(expr)?)?;
+            writeln!(out, "edited note {}", id)?;
+            changed = true;
+        }
+        Some(Commands::ReplaceLine { id, line, text }) => {
+            profile.edit_body(*id, &BodyEdit::ReplaceLine(*line, text.clone()))?;
+            writeln!(out, "edited note {}", id)?;
+            changed = true;
+        }
+        Some(Commands::Check { id, items, auto_status }) => {
+            let (done, total) =
+                profile.check_items(*id, items, true, *auto_status || config.auto_status.unwrap_or(false))?;
+            writeln!(out, "edited note {}, {}/{} checked", id, done, total)?;
+            changed = true;
+        }
+        Some(Commands::Uncheck { id, items, auto_status }) => {
+            let (done, total) =
+                profile.check_items(*id, items, false, *auto_status || config.auto_status.unwrap_or(false))?;
+            writeln!(out, "edited note {}, {}/{} checked", id, done, total)?;
+            changed = true;
+        }
+        Some(Commands::Del { id, children }) => {
+            let descendants = profile.descendants(id);
+            let children = match children {
+                _ if descendants.is_empty() => ChildAction::Keep,
+                Some(c) => *c,
+                None if cli.yes || !utils::istty(utils::STDIN_FILENO) => ChildAction::Keep,
+                None => {
+                    let message = format!("{} child notes ({}), delete them too?",
+                                          if id.len() == 1 { format!("note {} has", id[0]) } else { "these notes have".to_string() },
+                                          descendants.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", "));
+                    if utils::get_yn_input(&message)? { ChildAction::Delete } else { ChildAction::Keep }
+                }
+            };
+            let ids = match children {
+                ChildAction::Delete => [id.as_slice(), &descendants].concat(),
+                ChildAction::Keep => id.clone(),
+            };
+            let deleted = profile.delete_note(&ids);
+            for nid in ids.iter() {
+                if deleted.contains(nid) {
+                    writeln!(out, "deleted note {}", nid)?;
+                } else {
+                    writeln!(out, "note {} doesn't exist", nid)?;
+                }
+            }
+            changed = true;
+        }
+        Some(Commands::Move { id, parent, .. }) => {
+            profile.set_parent(*id, *parent)?;
+            match parent {
+                Some(p) => writeln!(out, "moved note {} under note {}", id, p)?,
+                None => writeln!(out, "moved note {} to the top level", id)?,
+            }
+            changed = true;
         }
         Some(Commands::Transfer { id, target_profile }) => {
              // transfer_note saves both?
-             profile.transfer_note(*id, target_profile, &cli.profile, &cli.profile_folder, cli.key.as_ref(), cli.encrypted, cli.yes)?;
+             let new_id = profile.transfer_note(*id, target_profile, &cli.profile, &cli.profile_folder, cli.key.as_ref(), cli.encrypted, cli.yes)?;
              // transfer_note in profile.rs removes from self and saves target.
              // We need to save self.
-             profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &fingerprint)?;
+             writeln!(out, "transfered [{}: note {} -> {}: note {}]", cli.profile, id, target_profile, new_id)?;
+             changed = true;
         }
         Some(Commands::NewProfile { name }) => {
+             if name == notetemplate::TEMPLATES_FOLDER {
+                 return specific_fail!(format!("'{}' is the folder of the note templates, pick another name", name));
+             }
              // profile is empty from `from_scratch`
-             // Save it as `name`.
+             // Save an empty profile as `name`.
              let key = if cli.encrypted {
                  if let Some(k) = &cli.key {
                      Some(k.clone())
@@ -167,8 +398,9 @@ pub fn r#run() -> Result<()> {
              } else {
                  None
              };
-             profile.save_to_file(name, &cli.profile_folder, key.as_ref(), true, false, cli.yes, &0)?;
-             println!("created profile '{}'", name);
+             Profile { encrypted: cli.encrypted, notes: vec![] }
+                 .save_to_file(name, &cli.profile_folder, key.as_ref(), true, false, cli.yes, &0)?;
+             writeln!(out, "created profile '{}'", name)?;
         }
         Some(Commands::EncryptProfile { new_key }) => {
              if !profile.encrypted {
@@ -179,9 +411,9 @@ pub fn r#run() -> Result<()> {
                   let mut new_profile = profile.clone();
                   new_profile.encrypted = true;
                   new_profile.save_to_file(&cli.profile, &cli.profile_folder, Some(&key), false, true, cli.yes, &0)?;
-                  println!("encrypting '{}'", cli.profile);
+                  writeln!(out, "encrypting '{}'", cli.profile)?;
              } else {
-                 println!("Profile '{}' is already encrypted.", cli.profile);
+                 writeln!(out, "Profile '{}' is already encrypted.", cli.profile)?;
              }
         }
         Some(Commands::DecryptProfile) => {
@@ -189,38 +421,72 @@ pub fn r#run() -> Result<()> {
                   let mut new_profile = profile.clone();
                   new_profile.encrypted = false;
                   new_profile.save_to_file(&cli.profile, &cli.profile_folder, None, false, false, cli.yes, &0)?;
-                  println!("decrypting '{}'", cli.profile);
+                  writeln!(out, "decrypting '{}'", cli.profile)?;
              } else {
-                 println!("Profile '{}' is not encrypted.", cli.profile);
+                 writeln!(out, "Profile '{}' is not encrypted.", cli.profile)?;
              }
         }
-        Some(Commands::Search { pattern, search_body, regex, limit }) => {
+        Some(Commands::Search { pattern, search_body, regex, limit, format, template, sort, columns }) => {
              let flags = ProfileFlags {
+                sort: parse_option(sort, SortKey::parse_list)?,
+                columns: parse_option(columns, Field::parse_list)?,
+                template: template.clone().or_else(|| config.templates.list.clone()),
+                dates,
+                color,
+                theme: theme.clone(),
                 search_body: *search_body,
                 regex: *regex,
                 condensed: false, 
-                yaml: false, 
+                format: *format,
                 ..Default::default()
             };
-            profile.search_notes(pattern, limit.unwrap_or(0), flags, None)?;
+            let notes = profile.search_notes(pattern, limit.unwrap_or(0), &flags, None)?;
+            output::write_notes(&mut out, &notes, &flags, "nothing found")?;
+        }
+        Some(Commands::Links { id, format }) => {
+            let note = profile.get_note(*id)?;
+            let outgoing = links::outgoing(note, &profile.notes);
+            let incoming = links::incoming(*id, &profile.notes);
+            output::write_links(&mut out, *id, &outgoing, &incoming, &profile.notes, *format)?;
         }
-        Some(Commands::ListProfiles) => {
+        Some(Commands::CheckLinks { format }) => {
+            let broken = links::broken(&profile.notes);
+            output::write_broken_links(&mut out, &broken, *format)?;
+            if !broken.is_empty() {
+                failed = Some(format!("{} broken links", broken.len()));
+            }
+        }
+        Some(Commands::Graph { format, status }) => {
+            let notes = profile.list_notes(0, &ProfileFlags::default(), utils::extract_status(status.clone())?);
+            let graph = graph::Graph::new(&notes, &profile.notes);
+            out.extend(graph.render(*format, &cli.profile)?.into_bytes());
+        }
+        Some(Commands::ListProfiles { format }) => {
             let folder = utils::find_profile_folder(&cli.profile_folder)?;
-            utils::profiles_in_folder(&folder)?;
+            let profiles = utils::profiles_in_folder(&folder)?;
+            output::write_profiles(&mut out, &folder, &profiles, *format)?;
         }
-        Some(Commands::Info) => {
-            profile.stats(&cli.profile)?;
+        Some(Commands::Info { format }) => {
+            let stats = profile.stats(&cli.profile)?;
+            output::write_stats(&mut out, &stats, *format, &dates, color)?;
         }
         Some(Commands::Clear) => {
             profile.clear(cli.yes)?;
-            profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &fingerprint)?;
+            changed = true;
         }
-        Some(Commands::List { limit, datesort, reverse, yaml, condensed, status }) => {
+        Some(Commands::List { limit, datesort, reverse, yaml, condensed, status, format, template, sort, columns, tree }) => {
              let flags = ProfileFlags {
-                yaml: *yaml,
+                sort: parse_option(sort, SortKey::parse_list)?,
+                columns: parse_option(columns, Field::parse_list)?,
+                template: template.clone().or_else(|| config.templates.list.clone()),
+                dates,
+                color,
+                theme: theme.clone(),
+                format: if *yaml { OutputFormat::Yaml } else { *format },
                 condensed: *condensed,
                 datesort: *datesort,
                 reverse: *reverse,
+                tree: *tree,
                 ..Default::default()
              };
              let st = if let Some(s) = status {
@@ -228,22 +494,261 @@ pub fn r#run() -> Result<()> {
              } else {
                 None
              };
-             profile.list_notes(limit.unwrap_or(0), flags, st)?;
+             let notes = profile.list_notes(limit.unwrap_or(0), &flags, st);
+             output::write_notes(&mut out, &notes, &flags, empty_message(profile))?;
+        }
+        Some(Commands::Sync { dry_run, prefer, .. }) => {
+            profile.sync(&mut out, &cli.profile, &cli.profile_folder, *dry_run, *prefer)?;
+            changed = !*dry_run;
+        }
+        Some(Commands::Git { action }) => match action {
+            GitCommands::Init { remote } => {
+                if let Some(dir) = git::init(&cli.profile, &cli.profile_folder, remote)? {
+                    writeln!(out, "initialized git history in {}", dir.display())?;
+                }
+            }
+            GitCommands::Log { limit } => out.extend(git::log(&cli.profile, &cli.profile_folder, *limit)?.into_bytes()),
+            GitCommands::Push => {
+                git::push(&cli.profile, &cli.profile_folder)?;
+                writeln!(out, "pushed '{}'", cli.profile)?;
+            }
+            GitCommands::Pull => match git::pull(&cli.profile, &cli.profile_folder, profile, cli.key.as_ref())? {
+                git::Pulled::UpToDate => writeln!(out, "already up to date")?,
+                git::Pulled::FastForward => writeln!(out, "pulled '{}'", cli.profile)?,
+                git::Pulled::Merged { notes, conflicts } => {
+                    for c in conflicts.iter() {
+                        writeln!(out, "{}", c)?;
+                    }
+                    profile.notes = notes;
+                    profile.save_to_file(&cli.profile,
+                                         &cli.profile_folder,
+                                         cli.key.as_ref(),
+                                         false,
+                                         false,
+                                         cli.yes,
+                                         &session.fingerprint)?;
+                    let (_, path) = utils::profile_paths(&cli.profile, &cli.profile_folder)?;
+                    session.fingerprint = utils::profile_fingerprint(path)?;
+                    writeln!(out, "merged '{}'", cli.profile)?;
+                }
+            },
+        },
+        Some(Commands::Tui) => {
+            tui::run(profile, &cli.profile, &cli.profile_folder, cli.key.as_ref(), &mut session.fingerprint, &theme, color)?
+        }
+        Some(Commands::Shell { .. }) | Some(Commands::Batch { .. }) => {
+            return specific_fail_str!("shell and batch only run from the command line");
+        }
+        Some(Commands::View { action, name, format, template }) => match action {
+            Some(ViewCommands::Save { name, global, view }) => {
+                let path = if *global {
+                    Config::user_path()?
+                } else {
+                    Config::profile_path(&cli.profile, &cli.profile_folder)?
+                };
+                let mut config = Config::load(&path)?;
+                config.views.insert(name.clone(), view_from_args(view)?);
+                config.save(&path)?;
+                writeln!(out, "saved view '{}'", name)?;
+            }
+            Some(ViewCommands::Delete { name, global }) => {
+                let path = if *global {
+                    Config::user_path()?
+                } else {
+                    Config::profile_path(&cli.profile, &cli.profile_folder)?
+                };
+                let mut config = Config::load(&path)?;
+                if config.views.remove(name).is_none() {
+                    return specific_fail!(format!("view '{}' doesn't exist", name));
+                }
+                config.save(&path)?;
+                writeln!(out, "deleted view '{}'", name)?;
+            }
+            Some(ViewCommands::List) => {
+                let user = Config::load(&Config::user_path()?)?;
+                let prof = Config::load(&Config::profile_path(&cli.profile, &cli.profile_folder)?)?;
+                writeln!(out, "# views for {}", cli.profile)?;
+                for (name, view) in prof.views.iter() {
+                    writeln!(out, "    {} [profile]: {}", name, view)?;
+                }
+                for (name, view) in user.views.iter().filter(|&(n, _)| !prof.views.contains_key(n)) {
+                    writeln!(out, "    {} [user]: {}", name, view)?;
+                }
+            }
+            None => {
+                let name = match name {
+                    Some(n) => n,
+                    None => return specific_fail_str!("no view name given, try `theca view list`"),
+                };
+                let view = match config.views.get(name) {
+                    Some(v) => v,
+                    None => return specific_fail!(format!("view '{}' doesn't exist", name)),
+                };
+                let flags = ProfileFlags {
+                    format: *format,
+                    template: template.clone().or_else(|| config.templates.list.clone()),
+                    dates,
+                    color,
+                    theme: theme.clone(),
+                    ..view.flags()?
+                };
+                let notes = match view.search {
+                    Some(ref pattern) => profile.search_notes(pattern, view.limit, &flags, view.status)?,
+                    None => profile.list_notes(view.limit, &flags, view.status),
+                };
+                output::write_notes(&mut out, &notes, &flags, empty_message(profile))?;
+            }
+        },
+        Some(Commands::Export { format, columns, no_header, path }) => {
+            let columns = match columns {
+                Some(c) => Field::parse_list(c)?,
+                None => Field::all(),
+            };
+            let tsv = *format == ExportFormat::Tsv;
+            match (format, path) {
+                (ExportFormat::Html, Some(p)) => {
+                    export::export_html(Path::new(p), &cli.profile, &profile.notes, &dates)?;
+                    writeln!(out, "exported {} notes to {}", profile.notes.len(), p)?;
+                }
+                (ExportFormat::Html, None) => {
+                    return specific_fail_str!("html export needs a directory to write to");
+                }
+                (_, Some(p)) => export::export_table(File::create(p)?, &profile.notes, &columns, !no_header, tsv)?,
+                (_, None) => export::export_table(&mut out, &profile.notes, &columns, !no_header, tsv)?,
+            }
         }
-        Some(Commands::Sync) => {
-            profile.sync(&cli.profile, &cli.profile_folder)?;
-            profile.save_to_file(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, false, cli.yes, &fingerprint)?;
+        Some(Commands::Import { format, columns, path }) => {
+            let columns = match columns {
+                Some(c) => Some(Field::parse_list(c)?),
+                None => None,
+            };
+            let tsv = *format == ImportFormat::Tsv;
+            let report = if path == "-" {
+                export::import_table(profile, stdin(), columns.as_deref(), tsv)?
+            } else {
+                export::import_table(profile, File::open(path)?, columns.as_deref(), tsv)?
+            };
+            for (line, e) in report.errors.iter() {
+                eprintln!("row {}: {}", line, e);
+            }
+            if !report.imported.is_empty() {
+                changed = true;
+            }
+            writeln!(out, "imported {} notes", report.imported.len())?;
+            if !report.errors.is_empty() {
+                failed = Some(format!("{} rows could not be imported", report.errors.len()));
+            }
         }
         None => {
+            let default_template = if cli.id.is_some() {
+                &config.templates.view
+            } else {
+                &config.templates.list
+            };
+            let flags = ProfileFlags {
+                format: cli.format,
+                template: cli.template.clone().or_else(|| default_template.clone()),
+                dates,
+                color,
+                theme: theme.clone(),
+                raw: cli.raw,
+                ..Default::default()
+            };
             if let Some(id) = cli.id {
-                profile.view_note(id, false, false)?;
+                let backlinks = links::backlinks(id, &profile.notes);
+                output::write_note(&mut out, profile.get_note(id)?, &backlinks, &flags)?;
             } else {
                 // Default list
-                let flags = ProfileFlags::default(); // defaults to false for json/condensed etc
-                profile.list_notes(0, flags, None)?;
+                let notes = profile.list_notes(0, &flags, None);
+                output::write_notes(&mut out, &notes, &flags, empty_message(profile))?;
+            }
+        }
+    }
+    if let Some(ref command) = cli.command {
+        let message = commit_message(command, &session.profile);
+        if changed {
+            session.unsaved.extend(message);
+            // a transferred note is in the other profile already
+            if session.autosave || matches!(command, Commands::Transfer { .. }) {
+                session.save(cli)?;
             }
+        } else if let Some(message) = message {
+            // commands that wrote a profile themselves
+            let name = match command {
+                Commands::NewProfile { name } => name,
+                _ => &cli.profile,
+            };
+            git::commit(name, &cli.profile_folder, &message)?;
+        }
+        if let Commands::Transfer { target_profile, .. } = command {
+            git::commit(target_profile, &cli.profile_folder, &format!("transfer note from {}", cli.profile))?;
+        }
+        if let Commands::Sync { watch: true, prefer, .. } = command {
+            // watching doesn't end, what the first sync did is shown now
+            utils::page_output(&out, false)?;
+            out.clear();
+            sync::watch(&cli.profile, &cli.profile_folder, cli.key.as_ref(), *prefer)?;
+        }
+    }
+    if let Some(message) = failed {
+        // the error replaces the output, what was collected is shown first
+        utils::page_output(&out, !cli.no_pager)?;
+        return specific_fail!(message);
+    }
+    Ok(out)
+}
+
+/// the message changes made by a command are committed with when the profile
+/// has git history, `None` for commands that don't change the profile
+fn commit_message(command: &Commands, profile: &Profile) -> Option<String> {
+    match command {
+        Commands::Add { .. } => profile.notes.last().map(|n| format!("add note {}", n.id)),
+        Commands::Edit { id, .. } | Commands::Append { id, .. } | Commands::Prepend { id, .. } |
+        Commands::Sed { id, .. } | Commands::ReplaceLine { id, .. } | Commands::Check { id, .. } |
+        Commands::Uncheck { id, .. } => Some(format!("edit note {}", id)),
+        Commands::Del { id, .. } if id.len() == 1 => Some(format!("delete note {}", id[0])),
+        Commands::Del { id, .. } => {
+            Some(format!("delete notes {}", id.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ")))
         }
+        Commands::Move { id, .. } => Some(format!("move note {}", id)),
+        Commands::Transfer { id, target_profile } => Some(format!("transfer note {} to {}", id, target_profile)),
+        Commands::NewProfile { name } => Some(format!("add profile {}", name)),
+        Commands::EncryptProfile { .. } => Some("encrypt profile".to_string()),
+        Commands::DecryptProfile => Some("decrypt profile".to_string()),
+        Commands::Clear => Some("clear profile".to_string()),
+        Commands::Sync { dry_run: false, .. } => Some("sync markdown files".to_string()),
+        Commands::Import { .. } => Some("import notes".to_string()),
+        Commands::Git { action: GitCommands::Pull } => Some("merge remote changes".to_string()),
+        _ => None,
     }
+}
 
-    Ok(())
+/// what to print when a listing comes up empty
+fn empty_message(profile: &Profile) -> &'static str {
+    if profile.notes.is_empty() {
+        "this profile is empty"
+    } else {
+        "nothing found"
+    }
+}
+
+fn view_from_args(args: &ViewArgs) -> Result<View> {
+    Ok(View {
+        search: args.search.clone(),
+        search_body: args.search_body,
+        regex: args.regex,
+        status: utils::extract_status(args.status.clone())?,
+        datesort: args.datesort,
+        reverse: args.reverse,
+        limit: args.limit.unwrap_or(0),
+        condensed: args.condensed,
+        sort: checked_list(&args.sort, SortKey::parse_list)?,
+        columns: checked_list(&args.columns, Field::parse_list)?,
+    })
+}
+
+/// validate an optional comma separated list before it is saved as is
+fn checked_list<T>(s: &Option<String>, parse: fn(&str) -> Result<Vec<T>>) -> Result<Option<String>> {
+    parse_option(s, parse)?;
+    Ok(s.clone())
 }
@@ -11,21 +11,46 @@
 //   tries to construct a line format that won't overflow the console
 //   width.
 
+use std::collections::HashMap;
+
 use crate::errors::Result;
-use crate::item::{Item, Status};
-use crate::utils::termsize;
+use crate::item::{Field, Item, Status};
+use crate::theme::Theme;
+use crate::utils::{termsize, DateDisplay};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Debug, Default)]
 pub struct LineFormat {
     pub colsep: usize,
     pub id_width: usize,
     pub title_width: usize,
     pub status_width: usize,
     pub touched_width: usize,
+    pub created_width: usize,
+    pub due_width: usize,
+    pub tags_width: usize,
+    pub progress_width: usize,
+    pub columns: Vec<Field>,
+    /// how deep notes are nested in a tree listing, by id
+    pub depths: HashMap<usize, usize>,
+    pub dates: DateDisplay,
+    /// the theme rows are colored with, `None` for plain output
+    pub colors: Option<Theme>,
 }
 
 impl LineFormat {
     pub fn new(items: &[Item], condensed: bool, search: bool) -> Result<LineFormat> {
+        LineFormat::with_columns(items, &Field::list_default(), condensed, search)
+    }
+
+    /// like `new` but only for the given columns, in the given order
+    pub fn with_columns(items: &[Item],
+                        columns: &[Field],
+                        condensed: bool,
+                        search: bool)
+                        -> Result<LineFormat> {
+        if columns.contains(&Field::Body) {
+            return Err("body can't be used as a list column".into());
+        }
         // get termsize :>
         let console_width = termsize();
 
@@ -37,11 +62,9 @@ impl LineFormat {
         };
 
         let mut line_format = LineFormat {
-            colsep: colsep,
-            id_width: 0,
-            title_width: 0,
-            status_width: 0,
-            touched_width: 0,
+            colsep,
+            columns: columns.to_vec(),
+            ..Default::default()
         };
 
         // get length of longest id string
@@ -96,33 +119,131 @@ impl LineFormat {
             0
         };
 
-        // last_touched has fixed string length so no need for silly iter stuff
-        line_format.touched_width = if condensed {
+        // dates have fixed string lengths so no need for silly iter stuff
+        let date_width = if condensed {
             10 // condensed
         } else {
             19 // expanded
         };
+        line_format.touched_width = date_width;
+        line_format.created_width = date_width;
+        line_format.due_width = date_width;
+
+        // tags are shown comma separated, wide enough for the "tags" header
+        line_format.tags_width = items.iter().map(|n| n.field(Field::Tags).len()).max().unwrap_or(0);
+        if line_format.tags_width < 4 && !condensed {
+            line_format.tags_width = 4;
+        }
+
+        // hidden like the status column when no note has a checklist
+        line_format.progress_width = items.iter().map(|n| n.field(Field::Progress).len()).max().unwrap_or(0);
+        if line_format.progress_width > 0 && !condensed {
+            line_format.progress_width = line_format.progress_width.max("progress".len());
+        }
+
+        line_format.fit_to(console_width);
+        Ok(line_format)
+    }
+
+    /// show dates as set in `dates`, relative dates are sized to the
+    /// longest one shown
+    pub fn set_dates(&mut self, items: &[Item], dates: &DateDisplay) -> Result<()> {
+        self.dates = *dates;
+        if dates.relative {
+            // condensed output has no header to make room for
+            let condensed = self.colsep == 1;
+            for field in [Field::Touched, Field::Created, Field::Due] {
+                let mut width = match field {
+                    _ if condensed => 0,
+                    Field::Touched => "last touched".len(),
+                    _ => field.name().len(),
+                };
+                for n in items {
+                    width = width.max(n.display_field(field, dates)?.len());
+                }
+                match field {
+                    Field::Touched => self.touched_width = width,
+                    Field::Created => self.created_width = width,
+                    _ => self.due_width = width,
+                }
+            }
+            self.fit_to(termsize());
+        }
+        Ok(())
+    }
+
+    /// indent notes under their parents, `items` are in tree order
+    pub fn set_tree(&mut self, items: &[Item]) {
+        let parents: HashMap<usize, Option<usize>> = items.iter().map(|n| (n.id, n.parent)).collect();
+        for n in items {
+            let mut depth = 0;
+            let mut parent = n.parent;
+            // the depth is capped in case the parents loop
+            while let Some(p) = parent.filter(|p| parents.contains_key(p) && depth < items.len()) {
+                depth += 1;
+                parent = parents[&p];
+            }
+            let marker = if n.body.is_empty() { 0 } else { 4 };
+            self.title_width = self.title_width.max(n.title.len() + 2 * depth + marker);
+            self.depths.insert(n.id, depth);
+        }
+        self.fit_to(termsize());
+    }
+
+    /// how deep a note is nested, 0 outside of tree listings
+    pub fn depth(&self, id: usize) -> usize {
+        self.depths.get(&id).copied().unwrap_or(0)
+    }
 
-        // check to make sure our new line format isn't bigger than the console
-        let line_width = line_format.line_width();
+    // check to make sure our new line format isn't bigger than the console,
+    // the title gives up space first and then the tags
+    fn fit_to(&mut self, console_width: usize) {
+        let line_width = self.line_width();
         if console_width > 0 && line_width > console_width {
-            let diff = line_width - console_width;
-            if line_format.title_width > diff {
-                line_format.title_width -= diff;
-            } else {
-                line_format.title_width = 0;
+            let mut diff = line_width - console_width;
+            for field in [Field::Title, Field::Tags] {
+                if !self.columns.contains(&field) {
+                    continue;
+                }
+                let width = match field {
+                    Field::Title => &mut self.title_width,
+                    _ => &mut self.tags_width,
+                };
+                let cut = diff.min(*width);
+                *width -= cut;
+                diff -= cut;
             }
         }
+    }
 
-        Ok(line_format)
+    /// the width of a column, the status and progress columns are 0 wide and
+    /// hidden when no note has a status or a checklist
+    pub fn width(&self, field: Field) -> usize {
+        match field {
+            Field::Id => self.id_width,
+            Field::Title => self.title_width,
+            Field::Status => self.status_width,
+            Field::Touched => self.touched_width,
+            Field::Created => self.created_width,
+            Field::Due => self.due_width,
+            Field::Tags => self.tags_width,
+            Field::Progress => self.progress_width,
+            Field::Body => 0,
+        }
+    }
+
+    /// the columns that are actually shown
+    pub fn visible_columns(&self) -> Vec<Field> {
+        self.columns
+            .iter()
+            .cloned()
+            .filter(|c| !matches!(c, Field::Status | Field::Progress) || self.width(*c) != 0)
+            .collect()
     }
 
     pub fn line_width(&self) -> usize {
-        let columns = if self.status_width == 0 {
-            2 * self.colsep
-        } else {
-            3 * self.colsep
-        };
-        self.id_width + self.title_width + self.status_width + self.touched_width + columns
+        let columns = self.visible_columns();
+        let seperators = columns.len().saturating_sub(1) * self.colsep;
+        columns.iter().map(|c| self.width(*c)).sum::<usize>() + seperators
     }
 }
@@ -0,0 +1,229 @@
+// links.rs
+//   wiki style links between notes in their bodies. `[[12]]` links to note
+//   12 and `[[Some title]]` to the note with that title, links in fenced
+//   code blocks don't count. the markdown mirror writes links as relative
+//   links to the files of the other notes and turns those back into wiki
+//   links when the files are read.
+
+use std::collections::HashMap;
+
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+use crate::item::Item;
+
+/// A link from the body of one note to another note
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Link {
+    /// id of the note the link is in
+    pub from: usize,
+    /// what is between the brackets
+    pub text: String,
+    /// id of the note it links to, `None` when there is no such note
+    pub to: Option<usize>,
+}
+
+fn link_regex() -> Regex {
+    Regex::new(r"\[\[([^\[\]\n]+)\]\]").unwrap()
+}
+
+// a markdown link to a `.md` file, as written by `to_markdown`
+fn file_link_regex() -> Regex {
+    Regex::new(r"\[([^\[\]\n]+)\]\(([^()\s]+\.md)\)").unwrap()
+}
+
+// `f` applied to the lines of a body outside of fenced code
+fn map_lines<F: FnMut(&str) -> String>(body: &str, mut f: F) -> String {
+    let mut fence: Option<&str> = None;
+    let lines: Vec<String> = body.split('\n')
+                                 .map(|line| {
+                                     let trimmed = line.trim_start();
+                                     match fence {
+                                         Some(end) if trimmed.starts_with(end) => fence = None,
+                                         Some(_) => {}
+                                         None if trimmed.starts_with("```") => fence = Some("```"),
+                                         None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+                                         None => return f(line),
+                                     }
+                                     line.to_string()
+                                 })
+                                 .collect();
+    lines.join("\n")
+}
+
+/// the text of the links in a body, in the order they appear
+pub fn links(body: &str) -> Vec<String> {
+    let re = link_regex();
+    let mut found = vec![];
+    map_lines(body, |line| {
+        found.extend(re.captures_iter(line).map(|c| c[1].trim().to_string()));
+        line.to_string()
+    });
+    found
+}
+
+/// the note a link points to: the note with that id, or else the first note
+/// with that title, ignoring case
+pub fn resolve<'a>(text: &str, notes: &'a [Item]) -> Option<&'a Item> {
+    let text = text.trim();
+    text.parse::<usize>()
+        .ok()
+        .and_then(|id| notes.iter().find(|n| n.id == id))
+        .or_else(|| notes.iter().find(|n| n.title.to_lowercase() == text.to_lowercase()))
+}
+
+/// the links in the body of a note
+pub fn outgoing(note: &Item, notes: &[Item]) -> Vec<Link> {
+    links(&note.body).into_iter()
+                     .map(|text| {
+                         let to = resolve(&text, notes).map(|n| n.id);
+                         Link { from: note.id, text, to }
+                     })
+                     .collect()
+}
+
+/// the links to a note in the bodies of all notes
+pub fn incoming(id: usize, notes: &[Item]) -> Vec<Link> {
+    notes.iter().flat_map(|n| outgoing(n, notes)).filter(|l| l.to == Some(id)).collect()
+}
+
+/// the notes that link to a note, each once
+pub fn backlinks(id: usize, notes: &[Item]) -> Vec<&Item> {
+    notes.iter().filter(|n| n.id != id && outgoing(n, notes).iter().any(|l| l.to == Some(id))).collect()
+}
+
+/// every link that doesn't point to a note
+pub fn broken(notes: &[Item]) -> Vec<Link> {
+    notes.iter().flat_map(|n| outgoing(n, notes)).filter(|l| l.to.is_none()).collect()
+}
+
+/// The files links point to when notes are written to the mirror
+#[derive(Clone, Debug, Default)]
+pub struct LinkTargets {
+    /// the file of every note, relative to the mirror directory
+    paths: HashMap<usize, String>,
+    /// note ids by lowercase title, the first note with a title wins
+    titles: HashMap<String, usize>,
+}
+
+impl LinkTargets {
+    /// targets for the notes with a file in `paths`
+    pub fn new(notes: &[Item], paths: HashMap<usize, String>) -> LinkTargets {
+        let mut titles = HashMap::new();
+        for n in notes.iter().filter(|n| paths.contains_key(&n.id)) {
+            titles.entry(n.title.to_lowercase()).or_insert(n.id);
+        }
+        LinkTargets { paths, titles }
+    }
+
+    // the file a link points to, found like `resolve` finds notes
+    fn path(&self, text: &str) -> Option<&String> {
+        let text = text.trim();
+        text.parse::<usize>()
+            .ok()
+            .and_then(|id| self.paths.get(&id))
+            .or_else(|| self.titles.get(&text.to_lowercase()).and_then(|id| self.paths.get(id)))
+    }
+}
+
+/// a body as it is written to the file at `from`, with links as relative
+/// markdown links to the files of the notes. broken links are kept.
+pub fn to_markdown(body: &str, from: &str, targets: &LinkTargets) -> String {
+    let re = link_regex();
+    map_lines(body, |line| {
+        re.replace_all(line, |c: &Captures| match targets.path(&c[1]) {
+              Some(path) => format!("[{}]({})", &c[1], encode(&relative_path(from, path))),
+              None => c[0].to_string(),
+          })
+          .to_string()
+    })
+}
+
+/// a body read from the file at `from`, with markdown links to the files in
+/// `files` turned back into wiki links. only links showing the id or the
+/// title of their note are turned back, other links are the user's.
+pub fn from_markdown(body: &str, from: &str, files: &HashMap<String, (usize, Option<String>)>) -> String {
+    let re = file_link_regex();
+    map_lines(body, |line| {
+        re.replace_all(line, |c: &Captures| {
+              let text = &c[1];
+              let target = join_path(from, &decode(&c[2])).and_then(|p| files.get(&p));
+              match target {
+                  Some((id, _)) if text.trim() == id.to_string() => format!("[[{}]]", text),
+                  Some((_, Some(title))) if text.trim().to_lowercase() == title.to_lowercase() => {
+                      format!("[[{}]]", text)
+                  }
+                  _ => c[0].to_string(),
+              }
+          })
+          .to_string()
+    })
+}
+
+// the path of `to` relative to the folder of the file `from`, both relative
+// to the same directory
+fn relative_path(from: &str, to: &str) -> String {
+    let mut from_dir: Vec<&str> = from.split('/').collect();
+    from_dir.pop();
+    let to_parts: Vec<&str> = to.split('/').collect();
+    let common = from_dir.iter()
+                         .zip(to_parts.iter().take(to_parts.len() - 1))
+                         .take_while(|(a, b)| a == b)
+                         .count();
+    let mut parts = vec![".."; from_dir.len() - common];
+    parts.extend(&to_parts[common..]);
+    parts.join("/")
+}
+
+// the path a relative link in the file `from` points to, `None` when it
+// leaves the directory
+fn join_path(from: &str, link: &str) -> Option<String> {
+    let mut parts: Vec<&str> = from.split('/').collect();
+    parts.pop();
+    for part in link.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            p => parts.push(p),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+// percent encode what can't be in a markdown link destination
+fn encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for c in path.chars() {
+        if c.is_ascii_alphanumeric() || "-_./~".contains(c) {
+            encoded.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    encoded
+}
+
+fn decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = path.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(b) if bytes[i] == b'%' => {
+                decoded.push(b);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,355 @@
+// markdown.rs
+//   rendering of markdown note bodies for the terminal: headings, emphasis,
+//   lists, checkboxes, code, links, quotes and tables get terminal styling
+//   and paragraphs are wrapped to the terminal width.
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use crate::errors::Result;
+use crate::theme::style_ansi;
+
+struct Renderer {
+    width: usize,
+    color: bool,
+    out: String,
+    line: String,
+    // visible length of the line and of its prefix, `started` is set once
+    // the prefix has been written
+    line_len: usize,
+    prefix_len: usize,
+    started: bool,
+    // prefixes of every line in the current block (quotes, list indents)
+    prefixes: Vec<String>,
+    // replaces the innermost prefix on the first line of a list item
+    first_prefix: Option<String>,
+    pending_space: bool,
+    // a blank line is written before the next line of output
+    pending_blank: bool,
+    styles: Vec<&'static str>,
+    lists: Vec<Option<u64>>,
+    link: Option<(String, String)>,
+    in_code_block: bool,
+    first_cell: bool,
+}
+
+impl Renderer {
+    fn new(width: usize, color: bool) -> Renderer {
+        Renderer {
+            width: if width == 0 { usize::MAX } else { width },
+            color,
+            out: String::new(),
+            line: String::new(),
+            line_len: 0,
+            prefix_len: 0,
+            started: false,
+            prefixes: vec![],
+            first_prefix: None,
+            pending_space: false,
+            pending_blank: false,
+            styles: vec![],
+            lists: vec![],
+            link: None,
+            in_code_block: false,
+            first_cell: true,
+        }
+    }
+
+    fn active_styles(&self) -> Result<String> {
+        let mut ansi = String::new();
+        if self.color {
+            for s in self.styles.iter() {
+                ansi.push_str(&style_ansi(s)?);
+            }
+        }
+        Ok(ansi)
+    }
+
+    fn push_style(&mut self, style: &'static str) -> Result<()> {
+        self.styles.push(style);
+        if self.color {
+            self.line.push_str(&style_ansi(style)?);
+        }
+        Ok(())
+    }
+
+    fn pop_style(&mut self) -> Result<()> {
+        self.styles.pop();
+        if self.color {
+            self.line.push_str(&style_ansi("reset")?);
+            let active = self.active_styles()?;
+            self.line.push_str(&active);
+        }
+        Ok(())
+    }
+
+    fn start_line(&mut self) -> Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.write_pending_blank();
+        let prefix = match self.first_prefix.take() {
+            Some(first) => {
+                let outer = self.prefixes.len().saturating_sub(1);
+                self.prefixes[..outer].concat() + &first
+            }
+            None => self.prefixes.concat(),
+        };
+        self.started = true;
+        self.prefix_len = prefix.chars().count();
+        self.line_len = self.prefix_len;
+        self.line = prefix + &self.active_styles()?;
+        Ok(())
+    }
+
+    fn flush_line(&mut self) -> Result<()> {
+        if self.started {
+            if self.color && !self.styles.is_empty() {
+                self.line.push_str(&style_ansi("reset")?);
+            }
+            self.out.push_str(self.line.trim_end());
+            self.out.push('\n');
+        }
+        self.line.clear();
+        self.line_len = 0;
+        self.started = false;
+        self.pending_space = false;
+        Ok(())
+    }
+
+    fn blank_line(&mut self) -> Result<()> {
+        self.flush_line()?;
+        self.pending_blank = true;
+        Ok(())
+    }
+
+    fn write_pending_blank(&mut self) {
+        if self.pending_blank && !self.out.is_empty() {
+            let prefix = self.prefixes.concat();
+            self.out.push_str(prefix.trim_end());
+            self.out.push('\n');
+        }
+        self.pending_blank = false;
+    }
+
+    fn word(&mut self, word: &str) -> Result<()> {
+        let len = word.chars().count();
+        self.start_line()?;
+        let empty = self.line_len == self.prefix_len;
+        let space = usize::from(self.pending_space && !empty);
+        if !empty && self.line_len + space + len > self.width {
+            self.flush_line()?;
+            self.start_line()?;
+        } else if space == 1 {
+            self.line.push(' ');
+            self.line_len += 1;
+        }
+        self.line.push_str(word);
+        self.line_len += len;
+        self.pending_space = false;
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        if let Some((_, ref mut link_text)) = self.link {
+            link_text.push_str(text);
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.pending_space = true;
+        }
+        let mut words = text.split_whitespace().peekable();
+        while let Some(w) = words.next() {
+            self.word(w)?;
+            if words.peek().is_some() {
+                self.pending_space = true;
+            }
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.pending_space = true;
+        }
+        Ok(())
+    }
+
+    fn code_block(&mut self, code: &str) -> Result<()> {
+        self.write_pending_blank();
+        let prefix = self.prefixes.concat() + "    ";
+        for l in code.lines() {
+            if l.is_empty() {
+                self.out.push('\n');
+            } else if self.color {
+                self.out.push_str(&format!("{}{}{}{}\n", prefix, style_ansi("cyan")?, l, style_ansi("reset")?));
+            } else {
+                self.out.push_str(&format!("{}{}\n", prefix, l));
+            }
+        }
+        Ok(())
+    }
+
+    fn start(&mut self, tag: Tag) -> Result<()> {
+        match tag {
+            Tag::Paragraph => self.flush_line()?,
+            Tag::Heading { level, .. } => {
+                self.blank_line()?;
+                if self.color {
+                    self.push_style(match level {
+                        HeadingLevel::H1 | HeadingLevel::H2 => "bold underline",
+                        _ => "bold",
+                    })?;
+                } else {
+                    // without styling keep the markers so headings stand out
+                    self.push_style("")?;
+                    self.word(&"#".repeat(level as usize))?;
+                    self.pending_space = true;
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.flush_line()?;
+                self.write_pending_blank();
+                self.prefixes.push("│ ".to_string());
+                self.push_style("dim")?;
+            }
+            Tag::CodeBlock(_) => {
+                self.blank_line()?;
+                self.in_code_block = true;
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.blank_line()?;
+                } else {
+                    self.flush_line()?;
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line()?;
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.prefixes.push(" ".repeat(bullet.chars().count()));
+                self.first_prefix = Some(bullet);
+            }
+            Tag::Emphasis => self.push_style("italic")?,
+            Tag::Strong => self.push_style("bold")?,
+            Tag::Strikethrough => self.push_style("dim")?,
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.link = Some((dest_url.to_string(), String::new()));
+                self.push_style("underline blue")?;
+            }
+            Tag::Table(_) => self.blank_line()?,
+            Tag::TableHead => {
+                self.first_cell = true;
+                self.push_style("bold")?;
+            }
+            Tag::TableRow => self.first_cell = true,
+            Tag::TableCell => {
+                if !self.first_cell {
+                    self.text(" | ")?;
+                }
+                self.first_cell = false;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, tag: TagEnd) -> Result<()> {
+        match tag {
+            TagEnd::Paragraph => {
+                if self.lists.is_empty() {
+                    self.blank_line()?;
+                } else {
+                    self.flush_line()?;
+                }
+            }
+            TagEnd::Heading(_) => {
+                self.pop_style()?;
+                self.blank_line()?;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_line()?;
+                self.pop_style()?;
+                self.prefixes.pop();
+                self.blank_line()?;
+            }
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                self.blank_line()?;
+            }
+            TagEnd::List(_) => {
+                self.flush_line()?;
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line()?;
+                }
+            }
+            TagEnd::Item => {
+                self.flush_line()?;
+                self.prefixes.pop();
+                self.first_prefix = None;
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style()?,
+            TagEnd::Link | TagEnd::Image => {
+                self.pop_style()?;
+                if let Some((url, text)) = self.link.take() {
+                    if !url.is_empty() && url != text {
+                        self.push_style("dim")?;
+                        self.pending_space = true;
+                        self.word(&format!("({})", url))?;
+                        self.pop_style()?;
+                    }
+                }
+            }
+            TagEnd::TableHead => {
+                self.pop_style()?;
+                self.flush_line()?;
+            }
+            TagEnd::TableRow => self.flush_line()?,
+            TagEnd::Table => self.blank_line()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// render a markdown body for the terminal, wrapped to `width` columns (0
+/// for no wrapping) and styled when `color` is set
+pub fn render_markdown(body: &str, width: usize, color: bool) -> Result<String> {
+    let mut r = Renderer::new(width, color);
+    let parser = Parser::new_ext(body, Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS |
+                                       Options::ENABLE_STRIKETHROUGH);
+    for event in parser {
+        match event {
+            Event::Start(tag) => r.start(tag)?,
+            Event::End(tag) => r.end(tag)?,
+            Event::Text(t) if r.in_code_block => r.code_block(&t)?,
+            Event::Text(t) | Event::Html(t) | Event::InlineHtml(t) => r.text(&t)?,
+            Event::Code(c) => {
+                r.push_style("cyan")?;
+                r.word(&c)?;
+                r.pop_style()?;
+            }
+            Event::SoftBreak => r.pending_space = true,
+            Event::HardBreak => r.flush_line()?,
+            Event::Rule => {
+                r.blank_line()?;
+                r.word(&"─".repeat(r.width.min(40)))?;
+                r.blank_line()?;
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(ref mut first) = r.first_prefix {
+                    *first = if checked { "[x] " } else { "[ ] " }.to_string();
+                    if let Some(p) = r.prefixes.last_mut() {
+                        *p = "    ".to_string();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    r.flush_line()?;
+    let rendered = r.out.trim_end().to_string();
+    Ok(if rendered.is_empty() { rendered } else { rendered + "\n" })
+}
@@ -0,0 +1,245 @@
+// notetemplate.rs
+//   note templates, markdown files in the `templates` folder of the profile
+//   folder that `add --template` starts a note from. a frontmatter block
+//   can set the title, status and tags of the note. placeholders in the
+//   title, tags and body are filled in:
+//
+//   {title}          the title given to `add`
+//   {profile}        the name of the profile
+//   {date}, {time}   the current date and time, {date:%d.%m.%Y} takes a
+//                    strftime format
+//   {ask:name}       asked for when the note is added, or given with
+//                    `--var name=value`
+//   {{ and }}        literal braces
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{stdin, stdout, Write};
+use std::path::Path;
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::errors::Result;
+use crate::frontmatter;
+use crate::item::Status;
+use crate::utils::{istty, now_timestamp, DateDisplay, STDIN_FILENO};
+use crate::{specific_fail, specific_fail_str};
+
+/// name of the templates folder in the profile folder
+pub static TEMPLATES_FOLDER: &str = "templates";
+
+/// A note template
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoteTemplate {
+    /// the title of the note, `None` to use the title given to `add`
+    pub title: Option<String>,
+    pub status: Option<Status>,
+    pub tags: Vec<String>,
+    pub body: String,
+}
+
+/// What placeholders are filled in with
+#[derive(Clone, Debug, Default)]
+pub struct Placeholders {
+    pub title: String,
+    pub profile: String,
+    pub dates: DateDisplay,
+    /// the values of the `{ask:...}` placeholders
+    pub vars: HashMap<String, String>,
+}
+
+/// A note filled in from a template
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilledNote {
+    pub title: String,
+    pub status: Option<Status>,
+    pub tags: Vec<String>,
+    pub body: String,
+}
+
+impl NoteTemplate {
+    /// load the template `name` from the templates folder of a profile folder
+    pub fn load(profile_folder: &Path, name: &str) -> Result<NoteTemplate> {
+        let folder = profile_folder.join(TEMPLATES_FOLDER);
+        let path = folder.join(format!("{}.md", name));
+        if name.contains('/') || !path.is_file() {
+            let names = template_names(&folder);
+            if names.is_empty() {
+                return specific_fail!(format!("there is no template '{}', add templates as markdown files to {}",
+                                              name,
+                                              folder.display()));
+            }
+            return specific_fail!(format!("there is no template '{}', templates are {}", name, names.join(", ")));
+        }
+        NoteTemplate::parse(&fs::read_to_string(&path)?).map_err(|e| format!("template '{}': {}", name, e).into())
+    }
+
+    /// parse the content of a template file
+    pub fn parse(content: &str) -> Result<NoteTemplate> {
+        let (fm, body) = match frontmatter::parse(content) {
+            Some(parsed) => parsed,
+            None if content.starts_with("---\n") => {
+                return specific_fail_str!("the frontmatter isn't valid YAML, quote values with placeholders in them");
+            }
+            None => (Default::default(), content.to_string()),
+        };
+        let mut unknown: Vec<&str> = fm.extra.keys().map(String::as_str).collect();
+        for (key, set) in [("id", fm.id.is_some()),
+                           ("last_touched", fm.last_touched.is_some()),
+                           ("created", fm.created.is_some()),
+                           ("due", fm.due.is_some()),
+                           ("parent", fm.parent.is_some())] {
+            if set {
+                unknown.push(key);
+            }
+        }
+        if !unknown.is_empty() {
+            return specific_fail!(format!("a template can set title, status and tags, not {}", unknown.join(", ")));
+        }
+        let status = match fm.status {
+            Some(_) => Some(fm.status()?),
+            None => None,
+        };
+        let template = NoteTemplate { title: fm.title, status, tags: fm.tags, body };
+        // mistakes in placeholders show up when the template is loaded
+        template.fill(&Placeholders::default())?;
+        Ok(template)
+    }
+
+    /// the names of the `{ask:...}` placeholders, each once
+    pub fn variables(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = vec![];
+        let texts = self.title.iter().chain(self.tags.iter()).chain(std::iter::once(&self.body));
+        for text in texts {
+            for part in parts(text)? {
+                let name = match part {
+                    Part::Placeholder(p) if p.starts_with("ask:") => p["ask:".len()..].trim(),
+                    _ => continue,
+                };
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// the note with the placeholders filled in
+    pub fn fill(&self, values: &Placeholders) -> Result<FilledNote> {
+        let title = match self.title {
+            Some(ref t) => fill(t, values)?.replace('\n', ""),
+            None => values.title.clone(),
+        };
+        let mut tags = vec![];
+        for tag in self.tags.iter() {
+            let tag = fill(tag, values)?;
+            if !tag.trim().is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Ok(FilledNote { title, status: self.status, tags, body: fill(&self.body, values)? })
+    }
+}
+
+/// the values of the `{ask:...}` placeholders: those given as `name=value`
+/// and the rest asked for on the terminal
+pub fn ask_variables(names: &[String], given: &[String]) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    for var in given {
+        match var.split_once('=') {
+            Some((name, value)) => vars.insert(name.trim().to_string(), value.to_string()),
+            None => return specific_fail!(format!("'{}' isn't a variable, use name=value", var)),
+        };
+    }
+    for name in names {
+        if vars.contains_key(name) {
+            continue;
+        }
+        if !istty(STDIN_FILENO) {
+            return specific_fail!(format!("the template asks for '{}', give it with --var {}=...", name, name));
+        }
+        print!("{}: ", name);
+        stdout().flush()?;
+        let mut value = String::new();
+        stdin().read_line(&mut value)?;
+        vars.insert(name.clone(), value.trim_end_matches(['\n', '\r']).to_string());
+    }
+    Ok(vars)
+}
+
+// the templates in a templates folder
+fn template_names(folder: &Path) -> Vec<String> {
+    let mut names = vec![];
+    for entry in fs::read_dir(folder).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if let Some(stem) = path.file_stem().filter(|_| path.extension().is_some_and(|e| e == "md")) {
+            names.push(stem.to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    names
+}
+
+// a piece of a text with placeholders
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+// split a text into its text and placeholders, without their braces
+fn parts(text: &str) -> Result<Vec<Part<'_>>> {
+    let mut parts = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find(['{', '}']) {
+        parts.push(Part::Text(&rest[..start]));
+        let brace = &rest[start..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            parts.push(Part::Text(&brace[..1]));
+            rest = &brace[2..];
+            continue;
+        }
+        let end = match brace.find('}') {
+            Some(end) if brace.starts_with('{') => end,
+            _ => return specific_fail_str!("unmatched brace, write '{{' or '}}' for a brace"),
+        };
+        parts.push(Part::Placeholder(&brace[1..end]));
+        rest = &brace[end + 1..];
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+// a text with its placeholders filled in
+fn fill(text: &str, values: &Placeholders) -> Result<String> {
+    let now = now_timestamp();
+    let mut filled = String::new();
+    for part in parts(text)? {
+        let placeholder = match part {
+            Part::Text(t) => {
+                filled.push_str(t);
+                continue;
+            }
+            Part::Placeholder(p) => p,
+        };
+        let value = match placeholder.split_once(':') {
+            None if placeholder == "title" => values.title.clone(),
+            None if placeholder == "profile" => values.profile.clone(),
+            None if placeholder == "date" => values.dates.absolute(&now, "%Y-%m-%d")?,
+            None if placeholder == "time" => values.dates.absolute(&now, "%H:%M")?,
+            Some(("date", format)) | Some(("time", format)) => {
+                // chrono panics when writing a format it can't parse
+                if StrftimeItems::new(format).any(|i| i == Item::Error) {
+                    return specific_fail!(format!("invalid date format '{}'", format));
+                }
+                values.dates.absolute(&now, format)?
+            }
+            Some(("ask", name)) => values.vars.get(name.trim()).cloned().unwrap_or_default(),
+            _ => {
+                return specific_fail!(format!("unknown placeholder '{{{}}}' (title, profile, date, time, ask:name)",
+                                              placeholder))
+            }
+        };
+        filled.push_str(&value);
+    }
+    Ok(filled)
+}
@@ -0,0 +1,365 @@
+// output.rs
+//   rendering of notes, profile stats and profile lists. everything here
+//   writes to a caller supplied writer, either as the human readable text
+//   output or as one of the machine readable formats (json, yaml, ndjson).
+
+use std::io::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::{Serialize, Deserialize};
+
+use crate::{specific_fail, specific_fail_str};
+use crate::checklist;
+use crate::errors::Result;
+use crate::item::{Field, Item, Status};
+use crate::lineformat::LineFormat;
+use crate::links::Link;
+use crate::profile::{ProfileFlags, Stats};
+use crate::template::Template;
+use crate::theme::paint;
+use crate::markdown::render_markdown;
+use crate::utils::{format_field, istty, pretty_line, termsize, DateDisplay, ProfileInfo, STDOUT_FILENO};
+
+/// Output format for commands that print notes or profile information
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// human readable text
+    #[default]
+    Text,
+    /// a single pretty printed JSON document
+    Json,
+    /// a single YAML document
+    Yaml,
+    /// one compact JSON object per line
+    Ndjson,
+}
+
+impl OutputFormat {
+    pub fn is_machine(&self) -> bool {
+        *self != OutputFormat::Text
+    }
+}
+
+/// write a list of values in a machine readable format, an empty list is
+/// `[]` for json and yaml and no output at all for ndjson
+pub fn write_list<W: Write, T: Serialize>(output: &mut W,
+                                          values: &[T],
+                                          format: OutputFormat)
+                                          -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *output, values)?;
+            writeln!(output)?;
+        }
+        OutputFormat::Yaml => write!(output, "{}", serde_yaml::to_string(values)?)?,
+        OutputFormat::Ndjson => {
+            for v in values {
+                serde_json::to_writer(&mut *output, v)?;
+                writeln!(output)?;
+            }
+        }
+        OutputFormat::Text => return specific_fail_str!("text is not a machine readable format"),
+    }
+    Ok(())
+}
+
+/// write a single value in a machine readable format
+pub fn write_value<W: Write, T: Serialize>(output: &mut W,
+                                           value: &T,
+                                           format: OutputFormat)
+                                           -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *output, value)?;
+            writeln!(output)?;
+        }
+        OutputFormat::Yaml => write!(output, "{}", serde_yaml::to_string(value)?)?,
+        OutputFormat::Ndjson => {
+            serde_json::to_writer(&mut *output, value)?;
+            writeln!(output)?;
+        }
+        OutputFormat::Text => return specific_fail_str!("text is not a machine readable format"),
+    }
+    Ok(())
+}
+
+/// A note in the machine readable formats, the fields of the profile file
+/// and what is worked out from them
+#[derive(Serialize, Clone, Debug)]
+pub struct NoteOutput {
+    #[serde(flatten)]
+    pub note: Item,
+    /// the checklist items of the body, when it has any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    pub checked: usize,
+    pub total: usize,
+}
+
+impl NoteOutput {
+    pub fn new(note: &Item, dates: &DateDisplay) -> NoteOutput {
+        NoteOutput {
+            note: note.with_rfc3339_dates(dates),
+            progress: checklist::progress(&note.body).map(|(checked, total)| Progress { checked, total }),
+        }
+    }
+}
+
+fn write_header<W: Write>(output: &mut W, line_format: &LineFormat) -> Result<()> {
+    let column_seperator: String = " ".repeat(line_format.colsep);
+    let header_seperator: String = "-".repeat(line_format.line_width());
+    let style = match line_format.colors {
+        Some(ref theme) => theme.header.as_str(),
+        None => "",
+    };
+    let columns: Vec<String> = line_format.visible_columns()
+                                          .iter()
+                                          .map(|c| {
+                                              let name = match *c {
+                                                  Field::Touched => "last touched",
+                                                  c => c.name(),
+                                              };
+                                              format_field(name, line_format.width(*c), false)
+                                          })
+                                          .collect();
+
+    writeln!(output, "{}", paint(&format!("{}\n{}", columns.join(&column_seperator), header_seperator), style)?)?;
+    Ok(())
+}
+
+/// write a list of notes, `empty` is the text shown when there is nothing
+/// to list
+pub fn write_notes<W: Write>(output: &mut W,
+                             notes: &[Item],
+                             flags: &ProfileFlags,
+                             empty: &str)
+                             -> Result<()> {
+    if flags.format.is_machine() {
+        let notes: Vec<NoteOutput> = notes.iter().map(|n| NoteOutput::new(n, &flags.dates)).collect();
+        return write_list(output, &notes, flags.format);
+    }
+    if notes.is_empty() {
+        writeln!(output, "{}", empty)?;
+        return Ok(());
+    }
+    if let Some(ref template) = flags.template {
+        let template: Template = template.parse()?;
+        for n in notes.iter() {
+            writeln!(output, "{}", template.render_dates(n, flags.color, &flags.dates)?)?;
+        }
+        return Ok(());
+    }
+    let columns = if flags.columns.is_empty() {
+        Field::list_default()
+    } else {
+        flags.columns.clone()
+    };
+    let mut line_format = LineFormat::with_columns(notes, &columns, flags.condensed, flags.search_body)?;
+    line_format.set_dates(notes, &flags.dates)?;
+    if flags.tree {
+        line_format.set_tree(notes);
+    }
+    if flags.color {
+        line_format.colors = Some(flags.theme.clone());
+    }
+    if !flags.condensed {
+        write_header(output, &line_format)?;
+    }
+    for n in notes.iter() {
+        n.write(output, &line_format, flags.search_body)?;
+    }
+    Ok(())
+}
+
+/// write a full note, `backlinks` are the notes linking to it
+pub fn write_note<W: Write>(output: &mut W, note: &Item, backlinks: &[&Item], flags: &ProfileFlags) -> Result<()> {
+    if flags.format.is_machine() {
+        return write_value(output, &NoteOutput::new(note, &flags.dates), flags.format);
+    }
+    let tty = flags.color;
+    if let Some(ref template) = flags.template {
+        let template: Template = template.parse()?;
+        let rendered = template.render_dates(note, flags.color, &flags.dates)?;
+        write!(output, "{}", rendered)?;
+        if !rendered.ends_with('\n') {
+            writeln!(output)?;
+        }
+        return Ok(());
+    }
+    let (id_style, status_style) = if flags.color {
+        (flags.theme.id.as_str(), flags.theme.status(note.status))
+    } else {
+        ("", "")
+    };
+    let mut fields = vec![("id", paint(&note.id.to_string(), id_style)?),
+                          ("title", note.title.clone())];
+    if note.status != Status::Blank {
+        fields.push(("status", paint(&note.status.to_string(), status_style)?));
+    }
+    fields.push(("last touched", note.display_field(Field::Touched, &flags.dates)?));
+    for field in [Field::Created, Field::Due, Field::Tags, Field::Progress] {
+        let value = note.display_field(field, &flags.dates)?;
+        if !value.is_empty() {
+            fields.push((field.name(), value));
+        }
+    }
+    if !backlinks.is_empty() {
+        let linked: Vec<String> = backlinks.iter().map(|n| format!("{}: {}", n.id, n.title)).collect();
+        fields.push(("backlinks", linked.join(", ")));
+    }
+
+    for (name, value) in fields {
+        if flags.condensed {
+            pretty_line(output, &format!("{}: ", name), &format!("{}\n", value), tty)?;
+        } else {
+            pretty_line(output,
+                        &format!("{}\n{}\n", name, "-".repeat(name.len())),
+                        &format!("{}\n\n", value),
+                        tty)?;
+        }
+    }
+
+    // body, rendered as markdown on a terminal unless --raw is given
+    if !note.body.is_empty() {
+        let body = if !flags.raw && istty(STDOUT_FILENO) {
+            render_markdown(&note.body, termsize(), flags.color)?
+        } else {
+            format!("{}\n", note.body)
+        };
+        if flags.condensed {
+            pretty_line(output, "body: ", &body, tty)?;
+        } else {
+            pretty_line(output, "body\n----\n", &format!("{}\n", body), tty)?;
+        };
+    }
+    Ok(())
+}
+
+/// write the links from and to a note
+pub fn write_links<W: Write>(output: &mut W,
+                             id: usize,
+                             outgoing: &[Link],
+                             incoming: &[Link],
+                             notes: &[Item],
+                             format: OutputFormat)
+                             -> Result<()> {
+    #[derive(Serialize)]
+    struct Links<'a> {
+        outgoing: &'a [Link],
+        incoming: &'a [Link],
+    }
+    if format.is_machine() {
+        return write_value(output, &Links { outgoing, incoming }, format);
+    }
+    let title = |id: usize| notes.iter().find(|n| n.id == id).map_or("", |n| n.title.as_str());
+    let heading = format!("links from note {}", id);
+    writeln!(output, "{}\n{}", heading, "-".repeat(heading.len()))?;
+    if outgoing.is_empty() {
+        writeln!(output, "none")?;
+    }
+    let mut seen = vec![];
+    for link in outgoing {
+        match link.to {
+            Some(to) if seen.contains(&to) => {}
+            Some(to) => {
+                writeln!(output, "{:<4} {}", to, title(to))?;
+                seen.push(to);
+            }
+            None => writeln!(output, "{:<4} [[{}]] (broken)", "", link.text)?,
+        }
+    }
+    let heading = format!("links to note {}", id);
+    writeln!(output, "\n{}\n{}", heading, "-".repeat(heading.len()))?;
+    if incoming.is_empty() {
+        writeln!(output, "none")?;
+    }
+    let mut seen = vec![];
+    for link in incoming {
+        if !seen.contains(&link.from) {
+            writeln!(output, "{:<4} {}", link.from, title(link.from))?;
+            seen.push(link.from);
+        }
+    }
+    Ok(())
+}
+
+/// write the links that don't point to a note
+pub fn write_broken_links<W: Write>(output: &mut W, broken: &[Link], format: OutputFormat) -> Result<()> {
+    if format.is_machine() {
+        return write_list(output, broken, format);
+    }
+    if broken.is_empty() {
+        writeln!(output, "no broken links")?;
+    }
+    for link in broken {
+        writeln!(output, "note {}: [[{}]]", link.from, link.text)?;
+    }
+    Ok(())
+}
+
+/// write information about a profile
+pub fn write_stats<W: Write>(output: &mut W,
+                             stats: &Stats,
+                             format: OutputFormat,
+                             dates: &DateDisplay,
+                             color: bool)
+                             -> Result<()> {
+    if format.is_machine() {
+        let convert = |d: &String| dates.rfc3339(d).unwrap_or_else(|_| d.clone());
+        let stats = Stats {
+            oldest: stats.oldest.as_ref().map(convert),
+            newest: stats.newest.as_ref().map(convert),
+            ..stats.clone()
+        };
+        return write_value(output, &stats, format);
+    }
+    let tty = color;
+    pretty_line(output, "name: ", &format!("{}\n", stats.name), tty)?;
+    pretty_line(output, "encrypted: ", &format!("{}\n", stats.encrypted), tty)?;
+    pretty_line(output, "notes: ", &format!("{}\n", stats.notes), tty)?;
+    if stats.notes > 0 {
+        pretty_line(output,
+                    "statuses: ",
+                    &format!("none: {}, started: {}, urgent: {}, done: {}\n",
+                             stats.statuses.blank,
+                             stats.statuses.started,
+                             stats.statuses.urgent,
+                             stats.statuses.done),
+                    tty)?;
+        if let (Some(oldest), Some(newest)) = (&stats.oldest, &stats.newest) {
+            pretty_line(output,
+                        "note ages: ",
+                        &format!("oldest: {}, newest: {}\n",
+                                 dates.format(oldest)?,
+                                 dates.format(newest)?),
+                        tty)?;
+        }
+    }
+    Ok(())
+}
+
+/// write the profiles found in a profile folder
+pub fn write_profiles<W: Write>(output: &mut W,
+                                folder: &Path,
+                                profiles: &[ProfileInfo],
+                                format: OutputFormat)
+                                -> Result<()> {
+    if format.is_machine() {
+        return write_list(output, profiles, format);
+    }
+    writeln!(output, "# profiles in {}", folder.display())?;
+    for p in profiles {
+        if p.encrypted {
+            writeln!(output, "    {} [encrypted]", p.name)?;
+        } else {
+            writeln!(output, "    {}", p.name)?;
+        }
+    }
+    Ok(())
+}
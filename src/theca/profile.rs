@@ -12,21 +12,25 @@ use regex::Regex;
 
 // theca imports
 use crate::utils::istty;
-use crate::utils::{drop_to_editor, pretty_line, get_yn_input, sorted_print, localize_last_touched_string,
-            parse_last_touched, find_profile_folder, profile_fingerprint};
+use crate::utils::{drop_to_editor, get_yn_input, sort_notes, parse_last_touched, find_profile_folder, now_timestamp, DateDisplay, profile_fingerprint, profile_paths};
 use crate::{specific_fail, specific_fail_str};
 use crate::errors::Result;
 
 // Use the new crypt module
 use crate::crypt::{encrypt, decrypt};
-use crate::item::{Status, Item};
+use crate::checklist;
+use crate::item::{BodyEdit, Field, Item, SortKey, Status};
+use crate::frontmatter::Frontmatter;
+use crate::sync::{self, Action, Mirror, SyncPrefer, SyncState};
+use crate::theme::Theme;
+use crate::output::OutputFormat;
 
 pub use libc::{STDIN_FILENO, STDOUT_FILENO, STDERR_FILENO};
 
 /// datetime formating string
-pub static DATEFMT: &'static str = "%F %T %z";
+pub static DATEFMT: &str = "%F %T %z";
 /// short datetime formating string for printing
-pub static DATEFMT_SHORT: &'static str = "%F %T";
+pub static DATEFMT_SHORT: &str = "%F %T";
 
 /// Main container of a theca profile file
 #[derive(Deserialize, Serialize, Clone)]
@@ -35,32 +39,47 @@ pub struct Profile {
     pub notes: Vec<Item>,
 }
 
+#[derive(Default, Clone)]
 pub struct ProfileFlags {
     pub condensed: bool,
     pub datesort: bool,
     pub editor: bool,
     pub encrypted: bool,
-    pub yaml: bool,
+    pub format: OutputFormat,
+    pub template: Option<String>,
+    pub columns: Vec<Field>,
+    pub sort: Vec<SortKey>,
+    pub dates: DateDisplay,
+    pub color: bool,
+    pub theme: Theme,
+    pub raw: bool,
     pub regex: bool,
     pub reverse: bool,
+    pub append: bool,
+    pub tree: bool,
     pub search_body: bool,
     pub yes: bool,
 }
 
-impl Default for ProfileFlags {
-    fn default() -> Self {
-        ProfileFlags {
-            condensed: false,
-            datesort: false,
-            editor: false,
-            encrypted: false,
-            yaml: false,
-            regex: false,
-            reverse: false,
-            search_body: false,
-            yes: false,
-        }
-    }
+
+/// Summary of a profile as shown by `info`
+#[derive(Serialize, Clone, Debug)]
+pub struct Stats {
+    pub name: String,
+    pub encrypted: bool,
+    pub notes: usize,
+    pub statuses: StatusCounts,
+    pub oldest: Option<String>,
+    pub newest: Option<String>,
+}
+
+/// Number of notes with each status
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct StatusCounts {
+    pub blank: usize,
+    pub started: usize,
+    pub urgent: usize,
+    pub done: usize,
 }
 
 impl Profile {
@@ -78,7 +97,7 @@ impl Profile {
             create_dir(&profile_base_path)?;
         }
         Ok((Profile {
-            encrypted: encrypted,
+            encrypted,
             notes: vec![],
         },
             0u64))
@@ -90,56 +109,14 @@ impl Profile {
                              encrypted: bool)
                              -> Result<(Profile, u64)> {
         // set profile folder
-        let profile_base = find_profile_folder(profile_folder)?;
-        
-        let (profile_dir, profile_path) = if profile_name == "default" {
-            (profile_base.clone(), profile_base.join("profile.yaml"))
-        } else {
-            let dir = profile_base.join(profile_name);
-            let path = dir.join("profile.yaml");
-            (dir, path)
-        };
+        let (profile_dir, profile_path) = profile_paths(profile_name, profile_folder)?;
 
         // attempt to read profile
         if profile_path.is_file() {
             let mut file = File::open(&profile_path)?;
             let mut contents_buf = vec![];
             file.read_to_end(&mut contents_buf)?;
-            let contents = if encrypted {
-                let key_val = if let Some(k) = key {
-                    k.clone()
-                } else {
-                    crate::utils::get_password()?
-                };
-
-                // Decrypt
-                // 1. Read as UTF-8 string (Base64)
-                let b64_str = String::from_utf8(contents_buf)
-                   .map_err(|_| "Failed to read encrypted file as UTF-8/Base64. Is it a legacy binary?")?;
-                // 2. Decode Base64
-                let encrypted_bytes = general_purpose::STANDARD.decode(b64_str.trim())
-                   .map_err(|e| {
-                       if b64_str.contains("encrypted:") {
-                           format!("Profile on disk appears to be plaintext (found 'encrypted:' key), but --encrypted was specified. Try without --encrypted. Original error: {}", e)
-                       } else {
-                           format!("Base64 decode error: {}", e)
-                       }
-                   })?;
-                // 3. Decrypt
-                match decrypt(&encrypted_bytes, &key_val) {
-                   Ok(decrypted) => String::from_utf8(decrypted)?,
-                   Err(_) => return specific_fail_str!("Decryption failed. Wrong key?"),
-                }
-            } else {
-                String::from_utf8(contents_buf)?
-            };
-            
-            let decoded: Profile = match serde_yaml::from_str(&contents) {
-                Ok(s) => s,
-                Err(e) => {
-                    return specific_fail!(format!("invalid YAML in {}: {}", profile_path.display(), e))
-                }
-            };
+            let decoded = Profile::from_bytes(contents_buf, key, encrypted, &profile_path)?;
             let fingerprint = profile_fingerprint(&profile_path)?;
             Ok((decoded, fingerprint))
         } else if profile_dir.exists() && profile_name != "default" && !profile_path.exists() {
@@ -164,6 +141,48 @@ impl Profile {
         }
     }
 
+    /// decode the contents of a profile file, `profile_path` is only used in
+    /// error messages
+    pub fn from_bytes(contents_buf: Vec<u8>,
+                      key: Option<&String>,
+                      encrypted: bool,
+                      profile_path: &Path)
+                      -> Result<Profile> {
+        let contents = if encrypted {
+            let key_val = if let Some(k) = key {
+                k.clone()
+            } else {
+                crate::utils::get_password()?
+            };
+
+            // Decrypt
+            // 1. Read as UTF-8 string (Base64)
+            let b64_str = String::from_utf8(contents_buf)
+               .map_err(|_| "Failed to read encrypted file as UTF-8/Base64. Is it a legacy binary?")?;
+            // 2. Decode Base64
+            let encrypted_bytes = general_purpose::STANDARD.decode(b64_str.trim())
+               .map_err(|e| {
+                   if b64_str.contains("encrypted:") {
+                       format!("Profile on disk appears to be plaintext (found 'encrypted:' key), but --encrypted was specified. Try without --encrypted. Original error: {}", e)
+                   } else {
+                       format!("Base64 decode error: {}", e)
+                   }
+               })?;
+            // 3. Decrypt
+            match decrypt(&encrypted_bytes, &key_val) {
+               Ok(decrypted) => String::from_utf8(decrypted)?,
+               Err(_) => return specific_fail_str!("Decryption failed. Wrong key?"),
+            }
+        } else {
+            String::from_utf8(contents_buf)?
+        };
+        
+        match serde_yaml::from_str(&contents) {
+            Ok(s) => Ok(s),
+            Err(e) => specific_fail!(format!("invalid YAML in {}: {}", profile_path.display(), e)),
+        }
+    }
+
     /// setup a Profile struct
     pub fn new(profile_name: &str,
                profile_folder: &Option<String>,
@@ -183,7 +202,7 @@ impl Profile {
     pub fn clear(&mut self, yes: bool) -> Result<()> {
         if !yes {
             let message = "are you sure you want to delete all the notes in this profile?\n";
-            if !get_yn_input(&message)? {
+            if !get_yn_input(message)? {
                 return specific_fail_str!("ok bye ♥");
             }
         }
@@ -191,59 +210,37 @@ impl Profile {
         Ok(())
     }
 
-    fn sync_markdown_files(&self, profile_dir: &Path) -> Result<()> {
-        let mut kept_files = std::collections::HashSet::new();
-
-        for note in &self.notes {
-            let sanitized_title = crate::utils::sanitize_filename(&note.title);
-            let filename = format!("{}-{}.md", note.id, sanitized_title);
-            let file_path = profile_dir.join(&filename);
-
-            let mut content = String::from("---\n");
-            content.push_str(&format!("id: {}\n", note.id));
-            content.push_str(&format!("title: {}\n", note.title));
-            content.push_str(&format!("status: {}\n", note.status));
-            content.push_str(&format!("last_touched: {}\n", note.last_touched));
-            content.push_str("---\n");
-            content.push_str(&note.body);
-
-            // Fail gracefully
-            if let Ok(mut f) = File::create(&file_path) {
-                let _ = f.write_all(content.as_bytes());
-            }
-            kept_files.insert(filename);
-        }
-
-        // Cleanup orphans
-        if let Ok(entries) = std::fs::read_dir(profile_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().and_then(|e| e.to_str()) == Some("md") {
-                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if !kept_files.contains(name) {
-                            let _ = std::fs::remove_file(path);
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(())
+    // bring the markdown files up to date with the profile, files changed
+    // since the last sync are left for `sync` to settle
+    fn sync_markdown_files(&mut self, profile_dir: &Path, mirror: &Mirror) -> Result<()> {
+        let mirror_dir = mirror.dir(profile_dir);
+        let mut state = SyncState::load(profile_dir, mirror)?;
+        let files = sync::read_files(&mirror_dir, mirror, &state)?;
+        let actions = sync::plan(&self.notes, &files, &state, mirror, None);
+        sync::apply(self, &mirror_dir, mirror, &files, &actions, &mut state, true)?;
+        state.save(profile_dir)
     }
 
-    fn delete_markdown_files(&self, profile_dir: &Path) -> Result<()> {
-        if let Ok(entries) = std::fs::read_dir(profile_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().and_then(|e| e.to_str()) == Some("md") {
-                    let _ = std::fs::remove_file(path);
+    // remove the plaintext copies of the notes when a profile is encrypted,
+    // files that aren't notes of this profile are kept
+    fn delete_markdown_files(&self, profile_dir: &Path, mirror: &Mirror) -> Result<()> {
+        let mirror_dir = mirror.dir(profile_dir);
+        for file in sync::read_files(&mirror_dir, mirror, &SyncState::default())? {
+            if let Some(Frontmatter { id: Some(id), .. }) = file.frontmatter {
+                if self.notes.iter().any(|n| n.id == id) {
+                    std::fs::remove_file(mirror_dir.join(&file.name))?;
                 }
             }
         }
+        let state = profile_dir.join(sync::SYNC_STATE_FILE);
+        if state.exists() {
+            std::fs::remove_file(state)?;
+        }
         Ok(())
     }
 
     /// save the profile back to file (either plaintext or encrypted)
+    #[allow(clippy::too_many_arguments)]
     pub fn save_to_file(&mut self, 
                         profile_name: &str, 
                         profile_folder: &Option<String>, 
@@ -254,21 +251,12 @@ impl Profile {
                         fingerprint: &u64) -> Result<()> {
         
         // Enforce collision rules for named profiles
-        if profile_name != "default" {
-            if profile_name == "profile.yaml" || profile_name.ends_with(".md") {
+        if profile_name != "default"
+            && (profile_name == "profile.yaml" || profile_name.ends_with(".md")) {
                 return specific_fail!(format!("Invalid profile name '{}'. Profile names cannot be 'profile.yaml' or end in '.md'.", profile_name));
             }
-        }
 
-        let profile_base = find_profile_folder(profile_folder)?;
-        
-        let (profile_dir, profile_path) = if profile_name == "default" {
-            (profile_base.clone(), profile_base.join("profile.yaml"))
-        } else {
-            let dir = profile_base.join(profile_name);
-            let path = dir.join("profile.yaml");
-            (dir, path)
-        };
+        let (profile_dir, profile_path) = profile_paths(profile_name, profile_folder)?;
         
         // Create directory if it doesn't exist (only for named profiles)
         if profile_name != "default" && !profile_dir.exists() {
@@ -291,6 +279,11 @@ impl Profile {
             }
         }
 
+        for n in self.notes.iter_mut() {
+            n.normalize_dates();
+        }
+        let mirror = Mirror::load(profile_name, profile_folder)?;
+
         // open file
         let mut file = File::create(&profile_path)?;
 
@@ -313,16 +306,17 @@ impl Profile {
         file.write_all(&buffer)?;
 
         // Handle markdown export
-        if !self.encrypted {
-            let _ = self.sync_markdown_files(&profile_dir);
+        if !self.encrypted && mirror.enabled {
+            let _ = self.sync_markdown_files(&profile_dir, &mirror);
         } else if encrypting {
-            let _ = self.delete_markdown_files(&profile_dir);
+            let _ = self.delete_markdown_files(&profile_dir, &mirror);
         }
 
         Ok(())
     }
 
     /// transfer a note from the profile to another profile
+    #[allow(clippy::too_many_arguments)]
     pub fn transfer_note(&mut self, 
                         note_id: usize, 
                         target_profile_name: &str,
@@ -330,7 +324,7 @@ impl Profile {
                         profile_folder: &Option<String>,
                         key: Option<&String>,
                         encrypted: bool,
-                        yes: bool) -> Result<()> {
+                        yes: bool) -> Result<usize> {
         
         if current_profile_name == target_profile_name {
             return specific_fail!(format!("cannot transfer a note from a profile to itself"));
@@ -351,41 +345,38 @@ impl Profile {
 
         if let Some(pos) = self.notes.iter().position(|n| n.id == note_id) {
              let n = &self.notes[pos];
-             trans_profile.add_note(&n.title,
-                                    &[n.body.clone()],
-                                    Some(n.status),
-                                    false,
-                                    false,
-                                    false)?;
-             
+             let new_id = trans_profile.add_note(&n.title,
+                                                 std::slice::from_ref(&n.body),
+                                                 Some(n.status),
+                                                 false,
+                                                 false)?;
+             // keep the dates and tags, the parent id means nothing in the target
+             if let Some(t) = trans_profile.notes.iter_mut().find(|t| t.id == new_id) {
+                 t.created = n.created.clone();
+                 t.due = n.due.clone();
+                 t.tags = n.tags.clone();
+             }
+
              // Save target
              trans_profile.save_to_file(target_profile_name, profile_folder, key, false, false, yes, &trans_fingerprint)?;
              
-             // Remove from source
-             self.notes.remove(pos);
-             
-             println!("transfered [{}: note {} -> {}: note {}]",
-                  current_profile_name,
-                  note_id,
-                  target_profile_name,
-                  trans_profile.notes.last().map_or(0, |n| n.id));
-
+             // Remove from source, the children it leaves behind move up
+             let note = self.notes.remove(pos);
+             self.reparent_children(note.id, note.parent);
+             Ok(new_id)
         } else {
-            return specific_fail!(format!("Note {} not found", note_id));
+            specific_fail!(format!("Note {} not found", note_id))
         }
-        
-        Ok(())
     }
 
-    /// add a item to the profile
+    /// add a item to the profile, returns the id of the new note
     pub fn add_note(&mut self,
                     title: &str,
                     body: &[String],
                     status: Option<Status>,
                     use_stdin: bool,
-                    use_editor: bool,
-                    print_msg: bool)
-                    -> Result<()> {
+                    use_editor: bool)
+                    -> Result<usize> {
         let title = title.replace("\n", "").to_string();
 
         let body = if use_stdin {
@@ -403,7 +394,7 @@ impl Profile {
                 Some(n) => n.id + 1,
                 None => 1,
             };
-            drop_to_editor(&"".to_string(), Some(next_id), Some(&title))?
+            drop_to_editor("", Some(next_id), Some(&title))?
         } else {
             "".to_string()
         };
@@ -412,33 +403,78 @@ impl Profile {
             Some(n) => n.id,
             None => 0,
         };
+        let now = now_timestamp();
         self.notes.push(Item {
             id: new_id + 1,
-            title: title,
+            title,
             status: status.unwrap_or(Status::Blank),
-            body: body,
-            last_touched: chrono::Local::now().format(DATEFMT).to_string(),
+            body,
+            last_touched: now.clone(),
+            created: Some(now),
+            ..Default::default()
         });
-        if print_msg {
-            println!("note {} added", new_id + 1);
-        }
-        Ok(())
+        Ok(new_id + 1)
     }
 
-    /// delete an item from the profile
-    pub fn delete_note(&mut self, id: &[usize]) {
+    /// delete an item from the profile, its children move up to its parent.
+    /// returns the ids of the notes that existed and were deleted.
+    pub fn delete_note(&mut self, id: &[usize]) -> Vec<usize> {
+        let mut deleted = vec![];
         for nid in id.iter() {
-            let remove = self.notes
-                             .iter()
-                             .position(|n| &n.id == nid)
-                             .map(|e| self.notes.remove(e))
-                             .is_some();
-            if remove {
-                println!("deleted note {}", nid);
-            } else {
-                println!("note {} doesn't exist", nid);
+            let removed = self.notes
+                              .iter()
+                              .position(|n| &n.id == nid)
+                              .map(|e| self.notes.remove(e));
+            if let Some(note) = removed {
+                self.reparent_children(note.id, note.parent);
+                deleted.push(*nid);
+            }
+        }
+        deleted
+    }
+
+    // give the children of `id` a new parent
+    fn reparent_children(&mut self, id: usize, parent: Option<usize>) {
+        for n in self.notes.iter_mut().filter(|n| n.parent == Some(id)) {
+            n.parent = parent;
+        }
+    }
+
+    /// the ids of the children of the notes, their children and so on
+    pub fn descendants(&self, id: &[usize]) -> Vec<usize> {
+        let mut found: Vec<usize> = vec![];
+        let mut parents = id.to_vec();
+        while let Some(p) = parents.pop() {
+            for n in self.notes.iter().filter(|n| n.parent == Some(p)) {
+                if !found.contains(&n.id) && !id.contains(&n.id) {
+                    found.push(n.id);
+                    parents.push(n.id);
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// make a note the child of another note, or a top level note with
+    /// `None`
+    pub fn set_parent(&mut self, id: usize, parent: Option<usize>) -> Result<()> {
+        if !self.notes.iter().any(|n| n.id == id) {
+            return specific_fail!(format!("note {} doesn't exist", id));
+        }
+        if let Some(p) = parent {
+            if !self.notes.iter().any(|n| n.id == p) {
+                return specific_fail!(format!("note {} doesn't exist", p));
             }
+            if p == id || self.descendants(&[id]).contains(&p) {
+                return specific_fail!(format!("note {} is part of note {}, it can't be its parent", p, id));
+            }
+        }
+        if let Some(note) = self.notes.iter_mut().find(|n| n.id == id) {
+            note.parent = parent;
+            note.last_touched = now_timestamp();
         }
+        Ok(())
     }
 
     /// edit an item in the profile
@@ -469,14 +505,18 @@ impl Profile {
             self.notes[item_pos].status = *s;
         }
 
-        if let Some(b) = body {
-             self.notes[item_pos].body = b.clone();
-        } else if use_stdin {
-             let mut buf = String::new();
-             stdin().read_to_string(&mut buf)?;
-             self.notes[item_pos].body = buf;
-        } else if use_editor {
-            if istty(STDOUT_FILENO) && istty(STDIN_FILENO) {
+        let new_body = if use_stdin {
+            let mut buf = String::new();
+            stdin().read_to_string(&mut buf)?;
+            Some(buf)
+        } else {
+            body.clone()
+        };
+        if let Some(b) = new_body {
+            let note = &mut self.notes[item_pos];
+            note.body = if flags.append { BodyEdit::Append(b).apply(&note.body)? } else { b };
+        } else if use_editor
+            && istty(STDOUT_FILENO) && istty(STDIN_FILENO) {
                 if encrypted && !yes {
                     let message = format!("{0}\n\n{1}\n{2}\n\n{0}\n{3}\n",
                                           "## [WARNING] ##",
@@ -494,159 +534,109 @@ impl Profile {
                     self.notes[item_pos].body = new_body;
                 }
             }
-        }
 
         // update last_touched
-        self.notes[item_pos].last_touched = chrono::Local::now().format(DATEFMT).to_string();
-        println!("edited note {}", self.notes[item_pos].id);
+        self.notes[item_pos].last_touched = now_timestamp();
         Ok(())
     }
 
-    /// print information about the profile
-    pub fn stats(&mut self, name: &str) -> Result<()> {
-        let no_s = self.notes.iter().filter(|n| n.status == Status::Blank).count();
-        let started_s = self.notes
-                            .iter()
-                            .filter(|n| n.status == Status::Started)
-                            .count();
-        let urgent_s = self.notes
-                           .iter()
-                           .filter(|n| n.status == Status::Urgent)
-                           .count();
-        let tty = istty(STDOUT_FILENO);
-        if self.notes.is_empty() {
-             pretty_line("name: ", &format!("{}\n", name), tty)?;
-             pretty_line("encrypted: ", &format!("{}\n", self.encrypted), tty)?;
-             pretty_line("notes: ", "0\n", tty)?;
-        } else {
-            let min = match self.notes
-                                .iter()
-                                .min_by_key(|n| match parse_last_touched(&*n.last_touched) {
-                                    Ok(o) => o,
-                                    Err(_) => chrono::Local::now(),
-                                }) {
-                Some(n) => localize_last_touched_string(&*n.last_touched)?,
-                None => return specific_fail_str!("last_touched is not properly formated"),
-            };
-            let max = match self.notes
-                                .iter()
-                                .max_by_key(|n| match parse_last_touched(&*n.last_touched) {
-                                    Ok(o) => o,
-                                    Err(_) => chrono::Local::now(),
-                                }) {
-                Some(n) => localize_last_touched_string(&*n.last_touched)?,
-                None => return specific_fail_str!("last_touched is not properly formated"),
-            };
-            pretty_line("name: ", &format!("{}\n", name), tty)?;
-            pretty_line("encrypted: ", &format!("{}\n", self.encrypted), tty)?;
-            pretty_line("notes: ", &format!("{}\n", self.notes.len()), tty)?;
-            pretty_line("statuses: ",
-                             &format!("none: {}, started: {}, urgent: {}\n",
-                                      no_s,
-                                      started_s,
-                                      urgent_s),
-                             tty)?;
-            pretty_line("note ages: ",
-                             &format!("oldest: {}, newest: {}\n", min, max),
-                             tty)?;
-        }
+    /// change part of a note's body
+    pub fn edit_body(&mut self, id: usize, edit: &BodyEdit) -> Result<()> {
+        let note = match self.notes.iter_mut().find(|n| n.id == id) {
+            Some(n) => n,
+            None => return specific_fail!(format!("note {} doesn't exist", id)),
+        };
+        note.body = edit.apply(&note.body).map_err(|e| format!("note {}: {}", id, e))?;
+        note.last_touched = now_timestamp();
         Ok(())
     }
 
-    /// print a full item
-    pub fn view_note(&mut self, id: usize, yaml: bool, condensed: bool) -> Result<()> {
-        let id = id;
-        let note_pos = match self.notes.iter().position(|n| n.id == id) {
-            Some(i) => i,
+    /// check or uncheck checklist items of a note, counting from 1. with
+    /// `auto_status` the status follows the checklist. returns the checked
+    /// and total items afterwards.
+    pub fn check_items(&mut self,
+                       id: usize,
+                       items: &[usize],
+                       checked: bool,
+                       auto_status: bool)
+                       -> Result<(usize, usize)> {
+        let note = match self.notes.iter_mut().find(|n| n.id == id) {
+            Some(n) => n,
             None => return specific_fail!(format!("note {} doesn't exist", id)),
         };
-        if yaml {
-            println!("{}", serde_yaml::to_string(&self.notes[note_pos].clone()).unwrap());
-        } else {
-            let tty = istty(STDOUT_FILENO);
-
-            if condensed {
-                pretty_line("id: ", &format!("{}\n", self.notes[note_pos].id), tty)?;
-                pretty_line("title: ", &format!("{}\n", self.notes[note_pos].title), tty)?;
-                if self.notes[note_pos].status != Status::Blank {
-                pretty_line("status: ",
-                                 &format!("{}\n", self.notes[note_pos].status),
-                                 tty)?;
-                }
-                pretty_line("last touched: ",
-                             &format!("{}\n",
-                            localize_last_touched_string(
-                                &*self.notes[note_pos].last_touched
-                            )
-                        ?),
-                             tty)?;
-            } else {
-                pretty_line("id\n--\n", &format!("{}\n\n", self.notes[note_pos].id), tty)?;
-                pretty_line("title\n-----\n",
-                                 &format!("{}\n\n", self.notes[note_pos].title),
-                                 tty)?;
-                if self.notes[note_pos].status != Status::Blank {
-                    pretty_line("status\n------\n",
-                                     &format!("{}\n\n", self.notes[note_pos].status),
-                                     tty)?;
-                }
-                pretty_line("last touched\n------------\n",
-                                 &format!("{}\n\n",
-                                localize_last_touched_string(
-                                    &*self.notes[note_pos].last_touched
-                                )
-                            ?),
-                                 tty)?;
-            };
+        let mut body = note.body.clone();
+        for n in items {
+            body = checklist::set_checked(&body, *n, checked).map_err(|e| format!("note {}: {}", id, e))?;
+        }
+        note.body = body;
+        let (done, total) = checklist::progress(&note.body).unwrap_or_default();
+        if auto_status {
+            note.status = checklist::auto_status(note.status, done, total);
+        }
+        note.last_touched = now_timestamp();
+        Ok((done, total))
+    }
 
-            // body
-            if !self.notes[note_pos].body.is_empty() {
-                if condensed {
-                    pretty_line("body: ", &format!("{}\n", self.notes[note_pos].body), tty)?;
-                } else {
-                    pretty_line("body\n----\n",
-                                     &format!("{}\n\n", self.notes[note_pos].body),
-                                     tty)?;
-                };
+    /// collect information about the profile
+    pub fn stats(&self, name: &str) -> Result<Stats> {
+        let mut statuses = StatusCounts::default();
+        for n in self.notes.iter() {
+            match n.status {
+                Status::Blank => statuses.blank += 1,
+                Status::Started => statuses.started += 1,
+                Status::Urgent => statuses.urgent += 1,
+                Status::Done => statuses.done += 1,
             }
         }
-        Ok(())
+        let touched = |n: &&Item| parse_last_touched(&n.last_touched).unwrap_or_else(|_| chrono::Utc::now());
+        Ok(Stats {
+            name: name.to_string(),
+            encrypted: self.encrypted,
+            notes: self.notes.len(),
+            statuses,
+            oldest: self.notes.iter().min_by_key(touched).map(|n| n.last_touched.clone()),
+            newest: self.notes.iter().max_by_key(touched).map(|n| n.last_touched.clone()),
+        })
     }
 
-    /// print all notes in the profile
-    pub fn list_notes(&mut self,
+    /// get a single note
+    pub fn get_note(&self, id: usize) -> Result<&Item> {
+        match self.notes.iter().find(|n| n.id == id) {
+            Some(n) => Ok(n),
+            None => specific_fail!(format!("note {} doesn't exist", id)),
+        }
+    }
+
+    /// the notes in the profile, filtered and sorted for listing
+    pub fn list_notes(&self,
                       limit: usize,
-                      flags: ProfileFlags,
+                      flags: &ProfileFlags,
                       status: Option<Status>)
-                      -> Result<()> {
-        if !self.notes.is_empty() {
-            sorted_print(&mut self.notes.clone(), limit, flags, status)?;
-        } else if flags.yaml {
-            println!("[]");
-        } else {
-            println!("this profile is empty");
-        }
-        Ok(())
+                      -> Vec<Item> {
+        let mut notes = self.notes.clone();
+        sort_notes(&mut notes, limit, flags, status);
+        notes
     }
 
-    /// print notes search for in the profile
-    pub fn search_notes(&mut self,
+    /// the notes in the profile that match `pattern`, filtered and sorted
+    /// for listing
+    pub fn search_notes(&self,
                         pattern: &str,
                         limit: usize,
-                        flags: ProfileFlags,
+                        flags: &ProfileFlags,
                         status: Option<Status>)
-                        -> Result<()> {
-        let notes: Vec<Item> = if flags.regex {
-            let re = match Regex::new(&pattern[..]) {
+                        -> Result<Vec<Item>> {
+        let mut notes: Vec<Item> = if flags.regex {
+            let re = match Regex::new(pattern) {
                 Ok(r) => r,
                 Err(e) => return specific_fail!(format!("regex error: {}.", e)),
             };
             self.notes
                 .iter()
                 .filter(|n| if flags.search_body {
-                    re.is_match(&*n.body)
+                    re.is_match(&n.body)
                 } else {
-                    re.is_match(&*n.title)
+                    re.is_match(&n.title)
                 })
                 .cloned()
                 .collect()
@@ -654,156 +644,70 @@ impl Profile {
             self.notes
                 .iter()
                 .filter(|n| if flags.search_body {
-                    n.body.contains(&pattern[..])
+                    n.body.contains(pattern)
                 } else {
-                    n.title.contains(&pattern[..])
+                    n.title.contains(pattern)
                 })
                 .cloned()
                 .collect()
         };
-        if !notes.is_empty() {
-            sorted_print(&mut notes.clone(), limit, flags, status)?;
-        } else if flags.yaml {
-            println!("[]");
-        } else {
-            println!("nothing found");
-        }
-        Ok(())
+        sort_notes(&mut notes, limit, flags, status);
+        Ok(notes)
     }
-    /// sync notes with markdown files in the profile folder
-    pub fn sync(&mut self, profile_name: &str, profile_folder: &Option<String>) -> Result<()> {
+
+    /// sync notes with markdown files in the profile folder, what is done is
+    /// written to `output`. with `dry_run` only the plan is written.
+    pub fn sync<W: Write>(&mut self,
+                          output: &mut W,
+                          profile_name: &str,
+                          profile_folder: &Option<String>,
+                          dry_run: bool,
+                          prefer: Option<SyncPrefer>)
+                          -> Result<()> {
         if self.encrypted {
             return specific_fail_str!("synchronization is only supported for plaintext profiles");
         }
 
-        let profile_base = find_profile_folder(profile_folder)?;
-        
-        let (profile_dir, _profile_path) = if profile_name == "default" {
-            (profile_base.clone(), profile_base.join("profile.yaml"))
-        } else {
-            let dir = profile_base.join(profile_name);
-            let path = dir.join("profile.yaml");
-            (dir, path)
-        };
+        let (profile_dir, _profile_path) = profile_paths(profile_name, profile_folder)?;
 
         if !profile_dir.exists() {
             return specific_fail!(format!("profile directory {} does not exist", profile_dir.display()));
         }
 
-        let mut new_notes_raw: Vec<(String, String)> = vec![];
-        let mut seen_ids = std::collections::HashSet::new();
-        // updated_notes was unused in previous version, removing it
-        
-        let entries = std::fs::read_dir(&profile_dir)?;
-        for entry in entries.flatten() {
-            let path = entry.path();
-            
-            // Skip directories (only matters for default profile sync, but safe everywhere)
-            if path.is_dir() {
-                continue;
-            }
-
-            if path.extension().and_then(|e| e.to_str()) == Some("md") {
-                let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
-                let mut content = String::new();
-                if let Ok(mut f) = File::open(&path) {
-                    let _ = f.read_to_string(&mut content);
-                }
-
-                // Check for valid frontmatter
-                if content.starts_with("---\n") {
-                    let parts: Vec<&str> = content.splitn(3, "---\n").collect();
-                    if parts.len() == 3 {
-                        let frontmatter = parts[1];
-                        let body = parts[2].to_string();
-
-                        // Parse frontmatter manually to avoid heavy dependencies if possible
-                        let mut id = None;
-                        let mut title = None;
-                        let mut status = None;
-                        // last_touched will be updated if body/title changes
-
-                        for line in frontmatter.lines() {
-                            if line.starts_with("id: ") {
-                                id = line[4..].parse::<usize>().ok();
-                            } else if line.starts_with("title: ") {
-                                title = Some(line[7..].to_string());
-                            } else if line.starts_with("status: ") {
-                                let s_str = line[8..].trim();
-                                status = crate::utils::extract_status(if s_str.is_empty() { None } else { Some(s_str.to_string()) }).ok().flatten();
-                            }
-                        }
-
-                        if let Some(id_val) = id {
-                            seen_ids.insert(id_val);
-                            // Find in current notes
-                            if let Some(note) = self.notes.iter_mut().find(|n| n.id == id_val) {
-                                let mut changed = false;
-                                if let Some(t) = title {
-                                    if note.title != t {
-                                        note.title = t;
-                                        changed = true;
-                                    }
-                                }
-                                if let Some(s) = status {
-                                    if note.status != s {
-                                        note.status = s;
-                                        changed = true;
-                                    }
-                                }
-                                if note.body != body {
-                                    note.body = body;
-                                    changed = true;
-                                }
-
-                                if changed {
-                                    note.last_touched = chrono::Local::now().format(DATEFMT).to_string();
-                                }
-                            }
-                            continue;
-                        }
-                    }
-                }
-
-                // If we reach here, it's either invalid frontmatter or new note
-                new_notes_raw.push((filename, content));
-                let _ = std::fs::remove_file(path);
-            }
+        let mirror = Mirror::load(profile_name, profile_folder)?;
+        if !mirror.enabled {
+            return specific_fail_str!("the markdown mirror is disabled in config.yaml");
         }
-
-        // Deletion: keep only notes that were seen in valid md files
-        self.notes.retain(|n| seen_ids.contains(&n.id));
-
-        // Add new/invalid notes
-        for (filename, content) in new_notes_raw {
-            let mut title = if filename.ends_with(".md") {
-                filename[..filename.len() - 3].to_string()
-            } else {
-                filename
-            };
-            
-            // Discard any "<N>-" at the start of the filename
-            if let Some(pos) = title.find('-') {
-                if title[..pos].chars().all(|c| c.is_ascii_digit()) {
-                    title = title[pos+1..].to_string();
+        let mirror_dir = mirror.dir(&profile_dir);
+        let mut state = SyncState::load(&profile_dir, &mirror)?;
+        let files = sync::read_files(&mirror_dir, &mirror, &state)?;
+        let actions = sync::plan(&self.notes, &files, &state, &mirror, prefer);
+        let mut conflicts = 0;
+        for action in actions.iter() {
+            match *action {
+                Action::Unchanged { .. } => {}
+                Action::Conflict { .. } => {
+                    conflicts += 1;
+                    writeln!(output, "{}", action)?;
                 }
+                _ => writeln!(output, "{}", action)?,
             }
-
-            // If it had frontmatter but was "invalid" (e.g. no ID), strip it for the body
-            let body = if content.starts_with("---\n") {
-                 let parts: Vec<&str> = content.splitn(3, "---\n").collect();
-                 if parts.len() == 3 { parts[2].to_string() } else { content }
-            } else {
-                content
-            };
-
-            self.add_note(&title, &[body], None, false, false, false)?;
         }
 
-        // Final sync of markdown files to disk based on new profile state
-        self.sync_markdown_files(&profile_dir)?;
-
-        println!("synchronization complete");
+        if dry_run {
+            writeln!(output, "dry run, nothing was changed")?;
+        } else {
+            sync::apply(self, &mirror_dir, &mirror, &files, &actions, &mut state, false)?;
+            state.save(&profile_dir)?;
+            writeln!(output, "synchronization complete")?;
+        }
+        if conflicts > 0 {
+            writeln!(output,
+                     "{} conflict{} left as is, settle {} with --prefer profile or --prefer files",
+                     conflicts,
+                     if conflicts == 1 { "" } else { "s" },
+                     if conflicts == 1 { "it" } else { "them" })?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,232 @@
+// shell.rs
+//   `theca shell`, a prompt that runs theca commands against a profile that
+//   is loaded, and decrypted, only once. changes are written on `save` and
+//   when the shell exits, or after every command with --autosave.
+
+use clap::{CommandFactory, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::args::{Cli, Commands, GitCommands};
+use crate::errors::Result;
+use crate::item::Item;
+use crate::profile::Profile;
+use crate::theme::ColorChoice;
+use crate::utils::{find_profile_folder, page_output, profiles_in_folder};
+use crate::{execute, specific_fail, specific_fail_str, Session};
+
+/// the shell history, in the profile folder
+pub static HISTORY_FILE: &str = ".shell-history";
+static BUILTINS: [&str; 3] = ["save", "exit", "quit"];
+
+/// tab completion for shell lines: commands, note ids (also found by their
+/// title), titles for `search` and profile names for `transfer`
+pub struct ShellCompleter {
+    commands: Vec<String>,
+    notes: Vec<(usize, String)>,
+    profiles: Vec<String>,
+}
+
+impl ShellCompleter {
+    pub fn new(notes: &[Item], profiles: Vec<String>) -> ShellCompleter {
+        let mut commands: Vec<String> = Cli::command().get_subcommands().map(|c| c.get_name().to_string()).collect();
+        commands.extend(BUILTINS.iter().map(|b| b.to_string()));
+        ShellCompleter {
+            commands,
+            notes: notes.iter().map(|n| (n.id, n.title.clone())).collect(),
+            profiles,
+        }
+    }
+
+    /// where the word at `pos` starts and what it can be completed to
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let start = word_start(&line[..pos]);
+        let word = line[start..pos].trim_start_matches(['"', '\'']);
+        let before = shlex::split(&line[..start]).unwrap_or_default();
+        let args: Vec<&str> = before.iter().map(String::as_str).filter(|w| !w.starts_with('-')).collect();
+        let lower = word.to_lowercase();
+        let pair = |display: String, replacement: String| Pair { display, replacement };
+
+        let candidates = match args.as_slice() {
+            [] if word.starts_with(|c: char| c.is_ascii_digit()) => self.ids(word),
+            [] => {
+                self.commands.iter().filter(|c| c.starts_with(word)).map(|c| pair(c.clone(), c.clone())).collect()
+            }
+            ["edit" | "append" | "prepend" | "sed" | "replace-line" | "check" | "uncheck" | "transfer" | "links"] | ["del" | "move", ..] => self.ids(word),
+            ["transfer", _] => {
+                self.profiles.iter().filter(|p| p.starts_with(word)).map(|p| pair(p.clone(), p.clone())).collect()
+            }
+            ["search"] => {
+                self.notes
+                    .iter()
+                    .filter(|(_, t)| t.to_lowercase().starts_with(&lower))
+                    .map(|(_, t)| pair(t.clone(), shlex::try_quote(t).map(|q| q.to_string()).unwrap_or(t.clone())))
+                    .collect()
+            }
+            _ => vec![],
+        };
+        (start, candidates)
+    }
+
+    // note ids starting with `word`, or the ids of notes with `word` in
+    // their title
+    fn ids(&self, word: &str) -> Vec<Pair> {
+        let lower = word.to_lowercase();
+        self.notes
+            .iter()
+            .filter(|(id, title)| id.to_string().starts_with(word) || title.to_lowercase().contains(&lower))
+            .map(|(id, title)| Pair { display: format!("{}  {}", id, title), replacement: id.to_string() })
+            .collect()
+    }
+}
+
+// where the last word of `line` starts, quoted words can hold spaces
+fn word_start(line: &str) -> usize {
+    let mut start = 0;
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => start = i + c.len_utf8(),
+            None => {}
+        }
+    }
+    start
+}
+
+impl Completer for ShellCompleter {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for ShellCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for ShellCompleter {}
+
+impl Validator for ShellCompleter {}
+
+impl Helper for ShellCompleter {}
+
+/// read and run commands until `exit` or ctrl-d, the profile in `session` was
+/// loaded for `cli`
+pub fn run(cli: &Cli, mut session: Session) -> Result<()> {
+    let folder = find_profile_folder(&cli.profile_folder)?;
+    // lines hold titles and bodies, for encrypted profiles they are only
+    // kept in memory
+    let history = if session.profile.encrypted || cli.encrypted { None } else { Some(folder.join(HISTORY_FILE)) };
+    let mut editor: Editor<ShellCompleter, DefaultHistory> = Editor::new()?;
+    if let Some(ref history) = history {
+        // there is no history the first time
+        let _ = editor.load_history(history);
+    }
+    println!("theca shell for '{}', `help` lists the commands, `save` saves and `exit` saves and quits",
+             cli.profile);
+
+    loop {
+        let profiles = profiles_in_folder(&folder)?.into_iter().map(|p| p.name).collect();
+        editor.set_helper(Some(ShellCompleter::new(&session.profile.notes, profiles)));
+        let unsaved = if session.unsaved.is_empty() { "" } else { "*" };
+        let line = match editor.readline(&format!("{}{}> ", cli.profile, unsaved)) {
+            Ok(l) => l,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        let result = match line {
+            "exit" | "quit" => break,
+            "save" => session.save(cli).map(|_| println!("saved '{}'", cli.profile)),
+            _ => run_line(cli, &mut session, line),
+        };
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+        }
+    }
+
+    if let Some(ref history) = history {
+        editor.save_history(history)?;
+    }
+    if !session.unsaved.is_empty() {
+        session.save(cli)?;
+        println!("saved '{}'", cli.profile);
+    }
+    Ok(())
+}
+
+/// the arguments of a line run against the profile loaded for `cli`, with
+/// the profile, folder and key of `cli`. commands that need the profile to
+/// themselves are refused.
+pub fn line_cli(cli: &Cli, parsed: Cli) -> Result<Cli> {
+    if parsed.profile != "default" && parsed.profile != cli.profile {
+        return specific_fail!(format!("only '{}' is loaded, run another command for '{}'", cli.profile, parsed.profile));
+    }
+    match parsed.command {
+        Some(Commands::Shell { .. }) | Some(Commands::Batch { .. }) => {
+            return specific_fail_str!("shell and batch only run from the command line");
+        }
+        Some(Commands::EncryptProfile { .. }) | Some(Commands::DecryptProfile) => {
+            return specific_fail_str!("encrypt or decrypt the profile from the command line");
+        }
+        Some(Commands::Sync { watch: true, .. }) => return specific_fail_str!("sync --watch only runs from the command line"),
+        _ => {}
+    }
+    Ok(Cli {
+        command: parsed.command,
+        id: parsed.id,
+        yes: cli.yes || parsed.yes,
+        format: parsed.format,
+        template: parsed.template,
+        tz: parsed.tz.or(cli.tz.clone()),
+        relative: cli.relative || parsed.relative,
+        raw: parsed.raw,
+        no_pager: cli.no_pager || parsed.no_pager,
+        color: if parsed.color == ColorChoice::Auto { cli.color } else { parsed.color },
+        ..cli.clone()
+    })
+}
+
+// parse a line like the arguments of `theca` and run it, the profile and
+// key are the shell's
+fn run_line(cli: &Cli, session: &mut Session, line: &str) -> Result<()> {
+    let words = match shlex::split(line) {
+        Some(w) => w,
+        None => return specific_fail_str!("unbalanced quotes"),
+    };
+    let parsed = match Cli::try_parse_from(std::iter::once("theca".to_string()).chain(words)) {
+        Ok(p) => p,
+        // also --help and --version
+        Err(e) => return Ok(e.print()?),
+    };
+    let line_cli = line_cli(cli, parsed)?;
+
+    // these read or write the profile file themselves
+    let on_disk = matches!(line_cli.command, Some(Commands::Git { .. }) | Some(Commands::Tui));
+    if on_disk && !session.unsaved.is_empty() {
+        session.save(cli)?;
+    }
+    let out = execute(&line_cli, session)?;
+    page_output(&out, !line_cli.no_pager)?;
+    if let Some(Commands::Git { action: GitCommands::Pull }) = line_cli.command {
+        let (profile, fingerprint) =
+            Profile::new(&cli.profile, &cli.profile_folder, cli.key.as_ref(), false, cli.encrypted, cli.yes)?;
+        session.profile = profile;
+        session.fingerprint = fingerprint;
+    }
+    Ok(())
+}
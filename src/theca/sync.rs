@@ -0,0 +1,707 @@
+// sync.rs
+//   two way sync between a plaintext profile and the markdown files it is
+//   mirrored to. a hash of every note as of the last sync is kept next to
+//   profile.yaml so a note deleted from the files can be told apart from
+//   one that was never exported, and notes changed on both sides are
+//   reported as conflicts instead of being overwritten.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::{Serialize, Deserialize};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::errors::Result;
+use crate::frontmatter::{self, Frontmatter};
+use crate::git;
+use crate::item::{Item, Status};
+use crate::links::{self, LinkTargets};
+use crate::notetemplate::TEMPLATES_FOLDER;
+use crate::profile::Profile;
+use crate::utils::{extract_status, now_timestamp, parse_last_touched, profile_paths, sanitize_filename};
+
+/// name of the sync state file in the profile directory
+pub static SYNC_STATE_FILE: &str = ".sync-state.yaml";
+
+/// how long the files have to be quiet before `sync --watch` syncs
+pub static WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Which side wins when a note changed in both the profile and its file
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SyncPrefer {
+    Profile,
+    Files,
+}
+
+/// Where and how notes are mirrored to markdown files, the `mirror` section
+/// of `config.yaml`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Mirror {
+    /// set to false to not mirror the profile at all
+    pub enabled: bool,
+    /// directory of the files, relative to the profile directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    /// file name without `.md`, from {id}, {title}, {status} and {created}
+    pub filename: String,
+    /// put the files in a folder per status or per (first) tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<GroupBy>,
+}
+
+impl Default for Mirror {
+    fn default() -> Mirror {
+        Mirror {
+            enabled: true,
+            dir: None,
+            filename: "{id}-{title}".to_string(),
+            group_by: None,
+        }
+    }
+}
+
+/// The folders the mirrored files are grouped in
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Status,
+    Tag,
+}
+
+static FILENAME_FIELDS: [&str; 4] = ["id", "title", "status", "created"];
+
+impl Mirror {
+    /// the mirror settings of a profile, from the user and profile configs
+    pub fn load(profile_name: &str, profile_folder: &Option<String>) -> Result<Mirror> {
+        let mirror = Config::merged(profile_name, profile_folder)?.mirror.unwrap_or_default();
+        mirror.validate()?;
+        Ok(mirror)
+    }
+
+    /// check the file name pattern and directory
+    pub fn validate(&self) -> Result<()> {
+        let mut rest = self.filename.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => return Err(format!("unclosed '{{' in mirror filename '{}'", self.filename).into()),
+            };
+            let field = &rest[start + 1..end];
+            if !FILENAME_FIELDS.contains(&field) {
+                return Err(format!("unknown field '{{{}}}' in mirror filename (id, title, status, created)",
+                                   field)
+                               .into());
+            }
+            rest = &rest[end + 1..];
+        }
+        if self.filename.trim().is_empty() || self.filename.contains('/') {
+            return Err(format!("invalid mirror filename '{}', use group_by for folders", self.filename).into());
+        }
+        if self.dir.as_deref().is_some_and(|d| Path::new(d).components().any(|c| c.as_os_str() == "..")) {
+            return Err("the mirror dir has to be inside the profile directory".into());
+        }
+        Ok(())
+    }
+
+    /// the directory the files of a profile are mirrored to
+    pub fn dir(&self, profile_dir: &Path) -> PathBuf {
+        match self.dir {
+            Some(ref d) => profile_dir.join(d),
+            None => profile_dir.to_path_buf(),
+        }
+    }
+
+    // the folder a note's file goes in, if any
+    fn folder(&self, note: &Item) -> Option<String> {
+        match self.group_by? {
+            GroupBy::Status => Some(note.status.name().to_string()),
+            GroupBy::Tag => note.tags
+                                .first()
+                                .map(|t| sanitize_filename(t))
+                                .filter(|t| !t.is_empty())
+                                // the note templates have that folder
+                                .map(|t| if t == TEMPLATES_FOLDER { format!("{}_", t) } else { t }),
+        }
+    }
+
+    /// the path a note is mirrored to, relative to the mirror directory
+    pub fn path(&self, note: &Item) -> String {
+        let created = note.created.as_deref().and_then(|c| c.get(..10)).unwrap_or_default();
+        let name = self.filename
+                       .replace("{id}", &note.id.to_string())
+                       .replace("{title}", &sanitize_filename(&note.title))
+                       .replace("{status}", note.status.name())
+                       .replace("{created}", created);
+        match self.folder(note) {
+            Some(folder) => format!("{}/{}.md", folder, name),
+            None => format!("{}.md", name),
+        }
+    }
+
+    // whether a file was named by the mirror, those follow their note when
+    // it changes, files the user named are left alone
+    fn named(&self, id: usize, path: &str) -> bool {
+        let mut pattern = regex::escape(&self.filename).replace(r"\{id\}", &id.to_string());
+        for field in FILENAME_FIELDS {
+            pattern = pattern.replace(&format!(r"\{{{}\}}", field), "[^/]*");
+        }
+        let folder = if self.group_by.is_some() { "([^/]+/)?" } else { "" };
+        Regex::new(&format!("^{}{}\\.md$", folder, pattern)).is_ok_and(|re| re.is_match(path))
+    }
+}
+
+/// A note as of the last sync
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SyncEntry {
+    pub hash: String,
+    pub file: String,
+}
+
+/// The state of every note as of the last sync
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SyncState {
+    /// the mirror `dir` the state is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    #[serde(default)]
+    pub notes: BTreeMap<usize, SyncEntry>,
+}
+
+impl SyncState {
+    /// load the state of a profile directory, a missing file is an empty
+    /// state. so is a state for another mirror directory, the files there
+    /// were never exported.
+    pub fn load(profile_dir: &Path, mirror: &Mirror) -> Result<SyncState> {
+        let path = profile_dir.join(SYNC_STATE_FILE);
+        let state: SyncState = if path.exists() {
+            match serde_yaml::from_str(&fs::read_to_string(&path)?) {
+                Ok(s) => s,
+                Err(e) => return Err(format!("invalid YAML in {}: {}", path.display(), e).into()),
+            }
+        } else {
+            SyncState::default()
+        };
+        if state.dir != mirror.dir {
+            return Ok(SyncState { dir: mirror.dir.clone(), ..Default::default() });
+        }
+        Ok(state)
+    }
+
+    pub fn save(&self, profile_dir: &Path) -> Result<()> {
+        fs::write(profile_dir.join(SYNC_STATE_FILE), serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// A markdown file in the mirror directory
+#[derive(Clone, Debug)]
+pub struct MdFile {
+    /// path relative to the mirror directory
+    pub name: String,
+    /// `None` when the file has no frontmatter or it isn't valid
+    pub frontmatter: Option<Frontmatter>,
+    /// the body with links to other notes' files as wiki links
+    pub body: String,
+    /// the body as it is in the file
+    pub markdown: String,
+    /// the status of the folder the file was moved to, when grouping by status
+    pub folder_status: Option<Status>,
+}
+
+impl MdFile {
+    pub fn new(name: &str, content: &str) -> MdFile {
+        let (frontmatter, body) = match frontmatter::parse(content) {
+            Some((fm, body)) => (Some(fm), body),
+            None => (None, frontmatter::strip(content)),
+        };
+        MdFile { name: name.to_string(), frontmatter, markdown: body.clone(), body, folder_status: None }
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.frontmatter.as_ref().and_then(|fm| fm.id)
+    }
+
+    /// the note as written in the file, fields the file doesn't set are
+    /// taken from `base`
+    pub fn note(&self, base: &Item) -> Item {
+        let fm = self.frontmatter.clone().unwrap_or_default();
+        Item {
+            title: fm.title.clone().filter(|t| !t.trim().is_empty()).unwrap_or_else(|| base.title.clone()),
+            status: self.folder_status.or(fm.status().ok()).unwrap_or(base.status),
+            body: self.body.clone(),
+            created: fm.created.or_else(|| base.created.clone()),
+            due: fm.due,
+            tags: fm.tags,
+            parent: fm.parent,
+            ..base.clone()
+        }
+    }
+}
+
+/// the markdown files in a mirror directory, sorted by name. with `group_by`
+/// the folders in it are read too, except for the directories of other
+/// profiles.
+pub fn read_files(mirror_dir: &Path, mirror: &Mirror, state: &SyncState) -> Result<Vec<MdFile>> {
+    let mut files = vec![];
+    if !mirror_dir.is_dir() {
+        return Ok(files);
+    }
+    let mut dirs = vec![(mirror_dir.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = dirs.pop() {
+        for entry in fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(n) => format!("{}{}", prefix, n),
+                None => continue,
+            };
+            if path.is_dir() {
+                if prefix.is_empty() && mirror.group_by.is_some() && !name.starts_with('.') &&
+                   name != TEMPLATES_FOLDER && !path.join("profile.yaml").exists() {
+                    dirs.push((path, format!("{}/", name)));
+                }
+            } else if path.extension().and_then(|e| e.to_str()) == Some("md") {
+                if let Ok(content) = fs::read_to_string(&path) {
+                    let mut file = MdFile::new(&name, &content);
+                    // moving a file to another status folder changes its status
+                    let moved = file.id().and_then(|id| state.notes.get(&id)).is_some_and(|s| s.file != name);
+                    if mirror.group_by == Some(GroupBy::Status) && moved && !prefix.is_empty() {
+                        file.folder_status = extract_status(Some(prefix.trim_end_matches('/').to_string()))
+                                                 .ok()
+                                                 .flatten();
+                    }
+                    files.push(file);
+                }
+            }
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // links to the files of notes are the wiki links they were written from
+    let targets: HashMap<String, (usize, Option<String>)> =
+        files.iter()
+             .filter_map(|f| f.frontmatter.as_ref().and_then(|fm| Some((f.name.clone(), (fm.id?, fm.title.clone())))))
+             .collect();
+    for file in files.iter_mut() {
+        file.body = links::from_markdown(&file.markdown, &file.name, &targets);
+    }
+    Ok(files)
+}
+
+/// hash of the content of a note, when it was touched and created are left
+/// out as they don't make it a different note
+pub fn note_hash(note: &Item) -> String {
+    let mut hasher = Sha256::new();
+    let due = note.due.clone().unwrap_or_default();
+    for part in [note.title.as_str(), note.status.name(), &note.body, &due, &note.tags.join(",")] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    // only when set, so hashes from before parents existed still match
+    if let Some(p) = note.parent {
+        hasher.update(format!("parent {}", p).as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+// the file a note is exported to, files the user named keep their name
+fn export_filename(mirror: &Mirror, note: &Item, current: Option<&str>) -> String {
+    match current {
+        Some(name) if !mirror.named(note.id, name) => name.to_string(),
+        _ => mirror.path(note),
+    }
+}
+
+/// One step of a sync
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// the note and its file are the same
+    Unchanged { id: usize, file: String },
+    /// write the note to `to`, replacing `from`
+    Export { id: usize, from: Option<String>, to: String },
+    /// update the note from its file
+    Import { id: usize, file: String },
+    /// add a note for a file that doesn't belong to one
+    Create { file: String },
+    /// delete a note whose file was deleted
+    DeleteNote { id: usize },
+    /// delete the file of a note that was deleted
+    RemoveFile { id: usize, file: String },
+    /// changed on both sides, nothing is done
+    Conflict { id: usize, file: Option<String>, reason: &'static str },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Action::Unchanged { id, ref file } => write!(f, "note {} is up to date in {}", id, file),
+            Action::Export { id, ref from, ref to } => match *from {
+                Some(ref from) if from != to => write!(f, "export note {} to {} (was {})", id, to, from),
+                _ => write!(f, "export note {} to {}", id, to),
+            },
+            Action::Import { id, ref file } => write!(f, "import note {} from {}", id, file),
+            Action::Create { ref file } => write!(f, "add a note from {}", file),
+            Action::DeleteNote { id } => write!(f, "delete note {}, its file was deleted", id),
+            Action::RemoveFile { id, ref file } => write!(f, "remove {}, note {} was deleted", file, id),
+            Action::Conflict { id, ref file, reason } => match *file {
+                Some(ref file) => write!(f, "conflict: note {} {} ({})", id, reason, file),
+                None => write!(f, "conflict: note {} {}", id, reason),
+            },
+        }
+    }
+}
+
+/// work out what a sync would do, `prefer` settles conflicts
+pub fn plan(notes: &[Item],
+            files: &[MdFile],
+            state: &SyncState,
+            mirror: &Mirror,
+            prefer: Option<SyncPrefer>)
+            -> Vec<Action> {
+    let mut actions = vec![];
+
+    // the file of each note id, a copy of a file is a new note
+    let mut by_id: HashMap<usize, &MdFile> = HashMap::new();
+    let mut unclaimed = vec![];
+    for file in files {
+        match file.id() {
+            Some(id) => match by_id.get(&id) {
+                // the file the note was synced to last time keeps the id
+                Some(other) if state.notes.get(&id).map(|s| &s.file) == Some(&file.name) => {
+                    unclaimed.push(*other);
+                    by_id.insert(id, file);
+                }
+                Some(_) => unclaimed.push(file),
+                None => {
+                    by_id.insert(id, file);
+                }
+            },
+            None => unclaimed.push(file),
+        }
+    }
+
+    for note in notes {
+        let stored = state.notes.get(&note.id);
+        let note_hash = note_hash(note);
+        let action = match by_id.remove(&note.id) {
+            Some(file) => {
+                let file_hash = note_hash_of(file, note);
+                let export = || Action::Export {
+                    id: note.id,
+                    from: Some(file.name.clone()),
+                    to: export_filename(mirror, note, Some(&file.name)),
+                };
+                let import = || Action::Import { id: note.id, file: file.name.clone() };
+                if file_hash == note_hash && export_filename(mirror, note, Some(&file.name)) != file.name {
+                    // the layout changed, the file is moved
+                    export()
+                } else if file_hash == note_hash {
+                    Action::Unchanged { id: note.id, file: file.name.clone() }
+                } else if stored.map(|s| &s.hash) == Some(&file_hash) {
+                    export()
+                } else if stored.map(|s| &s.hash) == Some(&note_hash) {
+                    import()
+                } else {
+                    match prefer {
+                        Some(SyncPrefer::Profile) => export(),
+                        Some(SyncPrefer::Files) => import(),
+                        None => Action::Conflict {
+                            id: note.id,
+                            file: Some(file.name.clone()),
+                            reason: "changed in both the profile and its file",
+                        },
+                    }
+                }
+            }
+            None => match stored {
+                // never exported
+                None => Action::Export { id: note.id, from: None, to: mirror.path(note) },
+                Some(s) if s.hash == note_hash => Action::DeleteNote { id: note.id },
+                Some(_) => match prefer {
+                    Some(SyncPrefer::Profile) => Action::Export { id: note.id, from: None, to: mirror.path(note) },
+                    Some(SyncPrefer::Files) => Action::DeleteNote { id: note.id },
+                    None => Action::Conflict {
+                        id: note.id,
+                        file: None,
+                        reason: "changed in the profile but its file was deleted",
+                    },
+                },
+            },
+        };
+        actions.push(action);
+    }
+
+    // files of notes that aren't in the profile
+    let mut rest: Vec<&MdFile> = by_id.into_values().collect();
+    rest.sort_by(|a, b| a.name.cmp(&b.name));
+    for file in rest {
+        let id = file.id().unwrap_or_default();
+        let action = match state.notes.get(&id) {
+            None => Action::Create { file: file.name.clone() },
+            Some(s) if s.hash == note_hash_of(file, &Item::default()) => {
+                Action::RemoveFile { id, file: file.name.clone() }
+            }
+            Some(_) => match prefer {
+                Some(SyncPrefer::Profile) => Action::RemoveFile { id, file: file.name.clone() },
+                Some(SyncPrefer::Files) => Action::Create { file: file.name.clone() },
+                None => Action::Conflict {
+                    id,
+                    file: Some(file.name.clone()),
+                    reason: "was deleted from the profile but its file changed",
+                },
+            },
+        };
+        actions.push(action);
+    }
+    unclaimed.sort_by(|a, b| a.name.cmp(&b.name));
+    for file in unclaimed {
+        actions.push(Action::Create { file: file.name.clone() });
+    }
+    actions
+}
+
+fn note_hash_of(file: &MdFile, base: &Item) -> String {
+    note_hash(&file.note(base))
+}
+
+// the title of a note added from a file without one, the "<N>-" a file
+// name may start with is dropped
+fn title_from_filename(name: &str) -> String {
+    let name = name.rsplit('/').next().unwrap_or(name);
+    let title = name.strip_suffix(".md").unwrap_or(name);
+    match title.split_once('-') {
+        Some((n, rest)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => rest.to_string(),
+        _ => title.to_string(),
+    }
+}
+
+// write a note to `to`, keeping the keys the user added to the frontmatter
+// of `from`, and remove `from` if the file was moved
+fn write_note(mirror_dir: &Path,
+              note: &Item,
+              from: Option<&MdFile>,
+              to: &str,
+              targets: &LinkTargets)
+              -> Result<()> {
+    let mut fm = Frontmatter::from_item(note);
+    if let Some(extra) = from.and_then(|f| f.frontmatter.as_ref()).map(|fm| fm.extra.clone()) {
+        fm.extra = extra;
+    }
+    let path = mirror_dir.join(to);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, frontmatter::render(&fm, &links::to_markdown(&note.body, to, targets))?)?;
+    if let Some(from) = from.filter(|f| f.name != to) {
+        remove_file(mirror_dir, &from.name)?;
+    }
+    Ok(())
+}
+
+// remove a mirrored file and the folder it was in if that is empty now
+fn remove_file(mirror_dir: &Path, name: &str) -> Result<()> {
+    let path = mirror_dir.join(name);
+    fs::remove_file(&path)?;
+    if let Some(dir) = path.parent().filter(|d| *d != mirror_dir) {
+        let _ = fs::remove_dir(dir);
+    }
+    Ok(())
+}
+
+/// carry out a sync plan and update the sync state. with `export_only` the
+/// files are only brought up to date with the profile, as when saving, and
+/// changes made in the files are left for the next sync.
+pub fn apply(profile: &mut Profile,
+             mirror_dir: &Path,
+             mirror: &Mirror,
+             files: &[MdFile],
+             actions: &[Action],
+             state: &mut SyncState,
+             export_only: bool)
+             -> Result<()> {
+    let file = |name: &str| files.iter().find(|f| f.name == name);
+    let entry = |note: &Item, file: &str| SyncEntry { hash: note_hash(note), file: file.to_string() };
+    let targets = link_targets(profile, mirror, actions, state);
+    for action in actions {
+        match *action {
+            Action::Unchanged { id, file: ref name } => {
+                if let Some(note) = profile.notes.iter().find(|n| n.id == id) {
+                    // links change when the files they point to move
+                    if let Some(from) = file(name).filter(|f| f.markdown != links::to_markdown(&note.body, name, &targets)) {
+                        write_note(mirror_dir, note, Some(from), name, &targets)?;
+                    }
+                    state.notes.insert(id, entry(note, name));
+                }
+            }
+            Action::Export { id, ref from, ref to } => {
+                if let Some(note) = profile.notes.iter().find(|n| n.id == id) {
+                    write_note(mirror_dir, note, from.as_deref().and_then(file), to, &targets)?;
+                    state.notes.insert(id, entry(note, to));
+                }
+            }
+            Action::RemoveFile { id, ref file } => {
+                remove_file(mirror_dir, file)?;
+                state.notes.remove(&id);
+            }
+            _ if export_only => {}
+            Action::Import { id, file: ref name } => {
+                let from = file(name);
+                if let (Some(note), Some(from)) = (profile.notes.iter_mut().find(|n| n.id == id), from) {
+                    let mut updated = from.note(note);
+                    let touched = from.frontmatter
+                                      .as_ref()
+                                      .and_then(|fm| fm.last_touched.clone())
+                                      .filter(|t| parse_last_touched(t).is_ok());
+                    updated.last_touched = match touched.filter(|t| *t != note.last_touched) {
+                        // the user set last_touched by hand
+                        Some(t) => t,
+                        None => now_timestamp(),
+                    };
+                    updated.normalize_dates();
+                    *note = updated;
+                    let to = export_filename(mirror, note, Some(name));
+                    write_note(mirror_dir, note, Some(from), &to, &targets)?;
+                    state.notes.insert(id, entry(note, &to));
+                }
+            }
+            Action::DeleteNote { id } => {
+                profile.notes.retain(|n| n.id != id);
+                state.notes.remove(&id);
+            }
+            Action::Create { file: ref name } => {
+                if let Some(from) = file(name) {
+                    let fm = from.frontmatter.clone().unwrap_or_default();
+                    let title = fm.title.clone().filter(|t| !t.trim().is_empty())
+                                  .unwrap_or_else(|| title_from_filename(name));
+                    profile.add_note(&title, std::slice::from_ref(&from.body), fm.status().ok(), false, false)?;
+                    if let Some(note) = profile.notes.last_mut() {
+                        note.due = fm.due;
+                        note.tags = fm.tags;
+                        note.normalize_dates();
+                        // a copy of another note's file gets a name of its own
+                        let to = match from.id() {
+                            Some(_) => mirror.path(note),
+                            None => name.to_string(),
+                        };
+                        write_note(mirror_dir, note, Some(from), &to, &targets)?;
+                        state.notes.insert(note.id, entry(note, &to));
+                    }
+                }
+            }
+            Action::Conflict { .. } => {}
+        }
+    }
+    // conflicts keep their state until they are settled
+    let conflicts: Vec<usize> = actions.iter()
+                                       .filter_map(|a| match *a {
+                                           Action::Conflict { id, .. } => Some(id),
+                                           _ => None,
+                                       })
+                                       .collect();
+    state.notes.retain(|id, _| conflicts.contains(id) || profile.notes.iter().any(|n| n.id == *id));
+    Ok(())
+}
+
+// the files the notes will be in once a sync plan is carried out, for the
+// links between them
+fn link_targets(profile: &Profile, mirror: &Mirror, actions: &[Action], state: &SyncState) -> LinkTargets {
+    let mut paths: HashMap<usize, String> = HashMap::new();
+    for action in actions {
+        match *action {
+            Action::Unchanged { id, ref file } => {
+                paths.insert(id, file.clone());
+            }
+            Action::Export { id, ref to, .. } => {
+                paths.insert(id, to.clone());
+            }
+            Action::Import { id, ref file } => {
+                if let Some(note) = profile.notes.iter().find(|n| n.id == id) {
+                    paths.insert(id, export_filename(mirror, note, Some(file)));
+                }
+            }
+            Action::Conflict { id, .. } => {
+                if let Some(s) = state.notes.get(&id) {
+                    paths.insert(id, s.file.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    LinkTargets::new(&profile.notes, paths)
+}
+
+// the content of the files a sync reads and writes, compared before every
+// sync so our own writes don't trigger another one
+fn snapshot(profile_path: &Path, mirror_dir: &Path, mirror: &Mirror) -> Vec<(String, String)> {
+    let hash = |content: &[u8]| format!("{:x}", Sha256::digest(content));
+    let mut files = vec![("".to_string(), fs::read(profile_path).map(|c| hash(&c)).unwrap_or_default())];
+    for file in read_files(mirror_dir, mirror, &SyncState::default()).unwrap_or_default() {
+        let content = format!("{:?}{}", file.frontmatter, file.body);
+        files.push((file.name, hash(content.as_bytes())));
+    }
+    files
+}
+
+// load the profile from disk, sync and save it again. the profile is saved
+// with the fingerprint it was loaded with so changes made by another theca
+// in the meantime aren't overwritten.
+fn sync_once(profile_name: &str,
+             profile_folder: &Option<String>,
+             key: Option<&String>,
+             prefer: Option<SyncPrefer>)
+             -> Result<()> {
+    let (mut profile, fingerprint) = Profile::new(profile_name, profile_folder, key, false, false, false)?;
+    profile.sync(&mut std::io::stdout(), profile_name, profile_folder, false, prefer)?;
+    profile.save_to_file(profile_name, profile_folder, key, false, false, false, &fingerprint)?;
+    git::commit(profile_name, profile_folder, "sync markdown files")
+}
+
+/// sync whenever the markdown files or profile.yaml change, until
+/// interrupted. failed syncs are reported and retried on the next change.
+pub fn watch(profile_name: &str,
+             profile_folder: &Option<String>,
+             key: Option<&String>,
+             prefer: Option<SyncPrefer>)
+             -> Result<()> {
+    let (profile_dir, profile_path) = profile_paths(profile_name, profile_folder)?;
+    let mirror = Mirror::load(profile_name, profile_folder)?;
+    let mirror_dir = mirror.dir(&profile_dir);
+    fs::create_dir_all(&mirror_dir)?;
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&profile_dir, RecursiveMode::NonRecursive)?;
+    if mirror.group_by.is_some() || mirror_dir != profile_dir {
+        let mode = if mirror.group_by.is_some() { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(&mirror_dir, mode)?;
+    }
+    println!("watching {} for changes, press ctrl-c to stop", mirror_dir.display());
+
+    let watched = |path: &Path| path == profile_path || path.extension().and_then(|e| e.to_str()) == Some("md");
+    let mut last = snapshot(&profile_path, &mirror_dir, &mirror);
+    loop {
+        let event = match rx.recv() {
+            Ok(event) => event?,
+            Err(_) => return Err("watch error: the watcher stopped".into()),
+        };
+        if matches!(event.kind, EventKind::Access(_)) || !event.paths.iter().any(|p| watched(p)) {
+            continue;
+        }
+        // wait for editors and other writers to finish
+        while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+        if snapshot(&profile_path, &mirror_dir, &mirror) == last {
+            continue;
+        }
+        if let Err(e) = sync_once(profile_name, profile_folder, key, prefer) {
+            eprintln!("error: {}", e);
+        }
+        last = snapshot(&profile_path, &mirror_dir, &mirror);
+    }
+}
@@ -0,0 +1,246 @@
+// template.rs
+//   user defined output templates for list rows and note views.
+//
+//   {field}              the value of a field (id, title, status, body, touched,
+//                        created, due, tags)
+//   {field:mod:...}      the value with modifiers applied, in order:
+//                          N, <N, >N, ^N   pad and cut to exactly N chars
+//                          .N              cut to at most N chars, ending in "..."
+//                          upper, lower    change case
+//                          firstline       only the first line
+//                          relative        relative date ("3h ago"), dates only
+//                          date            date without the time, dates only
+//                          raw             the date as it is stored, dates only
+//   {?field}...{/}       only shown when the field is not empty
+//   {!field}...{/}       only shown when the field is empty
+//   {#style}             switch to a color or style (red, green, yellow, blue,
+//                        magenta, cyan, white, grey, bold, dim, italic,
+//                        underline, reset), only used when color is enabled
+//   {{ and }}            literal braces
+
+use std::str::FromStr;
+
+use crate::errors::{Error, Result};
+use crate::item::{Field, Item};
+use crate::theme::style_ansi;
+use crate::utils::DateDisplay;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Modifier {
+    Pad(Align, usize),
+    Cut(usize),
+    Upper,
+    Lower,
+    FirstLine,
+    Relative,
+    Date,
+    Raw,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Field(Field, Vec<Modifier>),
+    Cond(Field, bool, Vec<Node>),
+    Style(String),
+}
+
+/// A parsed output template
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Template> {
+        let mut chars: Vec<char> = s.chars().collect();
+        chars.reverse();
+        let nodes = parse_nodes(&mut chars, false)?;
+        Ok(Template { nodes })
+    }
+}
+
+fn parse_nodes(chars: &mut Vec<char>, nested: bool) -> Result<Vec<Node>> {
+    let mut nodes = vec![];
+    let mut text = String::new();
+    while let Some(c) = chars.pop() {
+        match c {
+            '{' if chars.last() == Some(&'{') => {
+                chars.pop();
+                text.push('{');
+            }
+            '}' if chars.last() == Some(&'}') => {
+                chars.pop();
+                text.push('}');
+            }
+            '{' => {
+                let mut tag = String::new();
+                loop {
+                    match chars.pop() {
+                        Some('}') => break,
+                        Some(c) => tag.push(c),
+                        None => return Err(format!("unclosed '{{{}' in template", tag).into()),
+                    }
+                }
+                if !text.is_empty() {
+                    nodes.push(Node::Text(text.clone()));
+                    text.clear();
+                }
+                if tag == "/" {
+                    if !nested {
+                        return Err("'{/}' without a matching '{?field}' in template".into());
+                    }
+                    return Ok(nodes);
+                } else if let Some(style) = tag.strip_prefix('#') {
+                    let style = if style.trim().is_empty() { "reset" } else { style };
+                    style_ansi(style).map_err(|e| format!("{} in template", e.desc))?;
+                    nodes.push(Node::Style(style.to_string()));
+                } else if let Some(name) = tag.strip_prefix('?') {
+                    nodes.push(Node::Cond(name.trim().parse()?, false, parse_nodes(chars, true)?));
+                } else if let Some(name) = tag.strip_prefix('!') {
+                    nodes.push(Node::Cond(name.trim().parse()?, true, parse_nodes(chars, true)?));
+                } else {
+                    let mut parts = tag.split(':');
+                    let field = parts.next().unwrap_or("").trim().parse()?;
+                    let mods = parts.map(parse_modifier).collect::<Result<Vec<Modifier>>>()?;
+                    nodes.push(Node::Field(field, mods));
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    if nested {
+        return Err("missing '{/}' in template".into());
+    }
+    if !text.is_empty() {
+        nodes.push(Node::Text(text));
+    }
+    Ok(nodes)
+}
+
+fn parse_modifier(m: &str) -> Result<Modifier> {
+    let m = m.trim();
+    let width = |s: &str| s.parse::<usize>().map_err(|_| Error::from(format!("invalid template modifier '{}'", m)));
+    match m {
+        "upper" => Ok(Modifier::Upper),
+        "lower" => Ok(Modifier::Lower),
+        "firstline" => Ok(Modifier::FirstLine),
+        "relative" => Ok(Modifier::Relative),
+        "date" => Ok(Modifier::Date),
+        "raw" => Ok(Modifier::Raw),
+        _ => {
+            if let Some(n) = m.strip_prefix('.') {
+                Ok(Modifier::Cut(width(n)?))
+            } else if let Some(n) = m.strip_prefix('<') {
+                Ok(Modifier::Pad(Align::Left, width(n)?))
+            } else if let Some(n) = m.strip_prefix('>') {
+                Ok(Modifier::Pad(Align::Right, width(n)?))
+            } else if let Some(n) = m.strip_prefix('^') {
+                Ok(Modifier::Pad(Align::Center, width(n)?))
+            } else {
+                Ok(Modifier::Pad(Align::Left, width(m)?))
+            }
+        }
+    }
+}
+
+fn cut(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else if width > 3 {
+        value.chars().take(width - 3).chain("...".chars()).collect()
+    } else {
+        value.chars().take(width).collect()
+    }
+}
+
+fn pad(value: &str, align: &Align, width: usize) -> String {
+    let value = cut(value, width);
+    let fill = width - value.chars().count();
+    match *align {
+        Align::Left => format!("{}{}", value, " ".repeat(fill)),
+        Align::Right => format!("{}{}", " ".repeat(fill), value),
+        Align::Center => format!("{}{}{}", " ".repeat(fill / 2), value, " ".repeat(fill - fill / 2)),
+    }
+}
+
+fn field_value(item: &Item, field: Field, mods: &[Modifier], dates: &DateDisplay) -> Result<String> {
+    let mut value = match field {
+        Field::Touched | Field::Created | Field::Due => {
+            let raw = item.field(field);
+            if raw.is_empty() || mods.contains(&Modifier::Raw) {
+                raw
+            } else if mods.contains(&Modifier::Relative) {
+                dates.relative(&raw)?
+            } else if mods.contains(&Modifier::Date) {
+                dates.absolute(&raw, "%F")?
+            } else {
+                dates.format(&raw)?
+            }
+        }
+        f => item.field(f),
+    };
+    for m in mods {
+        value = match *m {
+            Modifier::Pad(ref align, w) => pad(&value, align, w),
+            Modifier::Cut(w) => cut(&value, w),
+            Modifier::Upper => value.to_uppercase(),
+            Modifier::Lower => value.to_lowercase(),
+            Modifier::FirstLine => value.lines().next().unwrap_or("").to_string(),
+            Modifier::Relative | Modifier::Date | Modifier::Raw => value,
+        };
+    }
+    Ok(value)
+}
+
+fn render_nodes(nodes: &[Node],
+                item: &Item,
+                color: bool,
+                dates: &DateDisplay,
+                out: &mut String)
+                -> Result<()> {
+    for node in nodes {
+        match *node {
+            Node::Text(ref t) => out.push_str(t),
+            Node::Field(field, ref mods) => out.push_str(&field_value(item, field, mods, dates)?),
+            Node::Cond(field, negate, ref body) => {
+                if item.field(field).is_empty() == negate {
+                    render_nodes(body, item, color, dates, out)?;
+                }
+            }
+            Node::Style(ref style) => {
+                if color {
+                    out.push_str(&style_ansi(style)?);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Template {
+    /// render the template for a note, styles are only emitted when
+    /// `color` is set
+    pub fn render(&self, item: &Item, color: bool) -> Result<String> {
+        self.render_dates(item, color, &DateDisplay::default())
+    }
+
+    /// like `render` but with dates shown as set in `dates`
+    pub fn render_dates(&self, item: &Item, color: bool, dates: &DateDisplay) -> Result<String> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, item, color, dates, &mut out)?;
+        if color && self.nodes.iter().any(|n| matches!(*n, Node::Style(_))) {
+            out.push_str(&style_ansi("reset")?);
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,118 @@
+// theme.rs
+//   colors used for list and note output. a theme maps parts of the output
+//   to styles, a style is one or more space separated names like "red" or
+//   "bold cyan". themes can be changed in the `theme` section of config.yaml.
+
+use std::env::var;
+
+use clap::ValueEnum;
+use crossterm::Command;
+use crossterm::style::{Attribute, Color, SetAttribute, SetForegroundColor};
+use serde::{Serialize, Deserialize};
+
+use crate::errors::Result;
+use crate::item::Status;
+use crate::utils::{istty, STDOUT_FILENO};
+
+/// When to use colors
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    /// when stdout is a terminal and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// whether output should be colored
+    pub fn enabled(&self) -> bool {
+        match *self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                var("NO_COLOR").map_or(true, |v| v.is_empty()) && istty(STDOUT_FILENO)
+            }
+        }
+    }
+}
+
+/// Styles for the parts of list and note output
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub header: String,
+    pub id: String,
+    pub marker: String,
+    pub urgent: String,
+    pub started: String,
+    pub done: String,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            header: "bold".to_string(),
+            id: "cyan".to_string(),
+            marker: "blue".to_string(),
+            urgent: "red".to_string(),
+            started: "yellow".to_string(),
+            done: "dim".to_string(),
+        }
+    }
+}
+
+impl Theme {
+    /// the style of a row for a note with `status`, blank notes aren't styled
+    pub fn status(&self, status: Status) -> &str {
+        match status {
+            Status::Blank => "",
+            Status::Started => &self.started,
+            Status::Urgent => &self.urgent,
+            Status::Done => &self.done,
+        }
+    }
+
+    /// check that every style in the theme is valid
+    pub fn validate(&self) -> Result<()> {
+        for style in [&self.header, &self.id, &self.marker, &self.urgent, &self.started, &self.done] {
+            style_ansi(style)?;
+        }
+        Ok(())
+    }
+}
+
+/// the escape codes for a style, e.g. "bold red"
+pub fn style_ansi(style: &str) -> Result<String> {
+    let mut ansi = String::new();
+    for name in style.split_whitespace() {
+        let color = |c: Color, ansi: &mut String| SetForegroundColor(c).write_ansi(ansi);
+        let attr = |a: Attribute, ansi: &mut String| SetAttribute(a).write_ansi(ansi);
+        match name {
+            "red" => color(Color::Red, &mut ansi),
+            "green" => color(Color::Green, &mut ansi),
+            "yellow" => color(Color::Yellow, &mut ansi),
+            "blue" => color(Color::Blue, &mut ansi),
+            "magenta" => color(Color::Magenta, &mut ansi),
+            "cyan" => color(Color::Cyan, &mut ansi),
+            "white" => color(Color::White, &mut ansi),
+            "grey" | "gray" => color(Color::Grey, &mut ansi),
+            "bold" => attr(Attribute::Bold, &mut ansi),
+            "dim" => attr(Attribute::Dim, &mut ansi),
+            "italic" => attr(Attribute::Italic, &mut ansi),
+            "underline" => attr(Attribute::Underlined, &mut ansi),
+            "reverse" => attr(Attribute::Reverse, &mut ansi),
+            "reset" => attr(Attribute::Reset, &mut ansi),
+            _ => return Err(format!("unknown style '{}'", name).into()),
+        }?;
+    }
+    Ok(ansi)
+}
+
+/// `text` wrapped in a style and a reset, unstyled when `style` is empty
+pub fn paint(text: &str, style: &str) -> Result<String> {
+    if style.trim().is_empty() {
+        return Ok(text.to_string());
+    }
+    Ok(format!("{}{}{}", style_ansi(style)?, text, style_ansi("reset")?))
+}
@@ -0,0 +1,367 @@
+// tui.rs
+//   `theca tui`, a full screen view of a profile: a note list with vim keys,
+//   a live filter and a preview of the selected note. every change is saved
+//   with `save_to_file` right away, like the commands it stands in for.
+
+use std::io::{stdout, Stdout, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::errors::Result;
+use crate::git;
+use crate::item::{Item, Status};
+use crate::markdown::render_markdown;
+use crate::profile::{Profile, ProfileFlags};
+use crate::theme::{paint, Theme};
+use crate::utils::{format_field, istty, profile_fingerprint, profile_paths, DateDisplay, STDIN_FILENO, STDOUT_FILENO};
+use crate::{specific_fail, specific_fail_str};
+
+static HELP: &str = "j/k move  / filter  b/s/u/c status  e edit  D delete  t transfer  q quit";
+
+/// the indexes of the notes matching a filter, matched case insensitively
+/// against the title, tags and body
+pub fn filter_notes(notes: &[Item], filter: &str) -> Vec<usize> {
+    let filter = filter.to_lowercase();
+    notes.iter()
+         .enumerate()
+         .filter(|(_, n)| {
+             filter.is_empty() || n.title.to_lowercase().contains(&filter) ||
+             n.tags.iter().any(|t| t.to_lowercase().contains(&filter)) ||
+             n.body.to_lowercase().contains(&filter)
+         })
+         .map(|(i, _)| i)
+         .collect()
+}
+
+// what keys are typed into
+enum Mode {
+    Normal,
+    Filter,
+    // the descendants of the note to delete
+    ConfirmDelete(Vec<usize>),
+    Transfer(String),
+}
+
+// puts the terminal back when the tui exits, also on errors
+struct Screen {
+    out: Stdout,
+}
+
+impl Screen {
+    fn enter() -> Result<Screen> {
+        let mut out = stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(Screen { out })
+    }
+
+    // give the terminal back for the editor and take it again afterwards
+    fn suspend<T>(&mut self, f: impl FnOnce() -> T) -> Result<T> {
+        execute!(self.out, LeaveAlternateScreen, Show)?;
+        terminal::disable_raw_mode()?;
+        let result = f();
+        terminal::enable_raw_mode()?;
+        execute!(self.out, EnterAlternateScreen, Hide)?;
+        Ok(result)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, LeaveAlternateScreen, Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Tui<'a> {
+    profile: &'a mut Profile,
+    profile_name: &'a str,
+    profile_folder: &'a Option<String>,
+    key: Option<&'a String>,
+    fingerprint: u64,
+    theme: Theme,
+    color: bool,
+    filter: String,
+    mode: Mode,
+    // position in the filtered list and the first row shown
+    selected: usize,
+    offset: usize,
+    message: String,
+}
+
+impl Tui<'_> {
+    fn visible(&self) -> Vec<usize> {
+        filter_notes(&self.profile.notes, &self.filter)
+    }
+
+    fn current(&self) -> Option<&Item> {
+        self.visible().get(self.selected).map(|i| &self.profile.notes[*i])
+    }
+
+    fn paint(&self, text: &str, style: &str) -> Result<String> {
+        if self.color {
+            paint(text, style)
+        } else {
+            Ok(text.to_string())
+        }
+    }
+
+    fn draw(&mut self, out: &mut Stdout) -> Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let (cols, rows) = (cols as usize, rows as usize);
+        let height = rows.saturating_sub(2);
+        let list_width = (cols * 2 / 5).max(20).min(cols);
+        let preview_width = cols.saturating_sub(list_width + 3);
+
+        let visible = self.visible();
+        self.selected = self.selected.min(visible.len().saturating_sub(1));
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if height > 0 && self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        let header = format!("theca: {} ({} of {} notes)", self.profile_name, visible.len(), self.profile.notes.len());
+        let header = self.paint(&format_field(&header, cols, true), &self.theme.header)?;
+        queue!(out, Print(header))?;
+
+        let id_width = self.profile.notes.iter().map(|n| n.id.to_string().len()).max().unwrap_or(1);
+        for (row, i) in visible.iter().skip(self.offset).take(height).enumerate() {
+            let n = &self.profile.notes[*i];
+            let status = format!("{}", n.status).chars().next().unwrap_or(' ').to_string();
+            let title_width = list_width.saturating_sub(id_width + 3);
+            let line = format!("{:>w$} {} {}", n.id, status, format_field(&n.title, title_width, true), w = id_width);
+            let style = if row + self.offset == self.selected {
+                "reverse".to_string()
+            } else {
+                self.theme.status(n.status).to_string()
+            };
+            let line = if self.color || style == "reverse" { paint(&line, &style)? } else { line };
+            queue!(out, MoveTo(0, row as u16 + 1), Print(line))?;
+        }
+
+        if let Some(note) = self.current() {
+            let mut lines = vec![self.paint(&format_field(&note.title, preview_width, true), "bold")?];
+            let mut fields = vec![format!("#{}", note.id)];
+            if note.status != Status::Blank {
+                fields.push(note.status.name().to_string());
+            }
+            fields.push(DateDisplay::default().format(&note.last_touched)?);
+            if !note.tags.is_empty() {
+                fields.push(note.tags.join(", "));
+            }
+            lines.push(self.paint(&format_field(&fields.join("  "), preview_width, true), "dim")?);
+            lines.push(String::new());
+            lines.extend(render_markdown(&note.body, preview_width, self.color)?.lines().map(String::from));
+            for (row, line) in lines.iter().take(height).enumerate() {
+                queue!(out, MoveTo(list_width as u16 + 1, row as u16 + 1), Print("│ "), Print(line))?;
+            }
+            for row in lines.len().min(height)..height {
+                queue!(out, MoveTo(list_width as u16 + 1, row as u16 + 1), Print("│"))?;
+            }
+        }
+
+        let status_line = match self.mode {
+            Mode::Filter => format!("/{}", self.filter),
+            Mode::ConfirmDelete(ref children) if children.is_empty() => "delete this note? (y/n)".to_string(),
+            Mode::ConfirmDelete(ref children) => {
+                format!("delete this note and keep its {} child notes? (y/n, a deletes them too)", children.len())
+            }
+            Mode::Transfer(ref target) => format!("transfer to profile: {}", target),
+            Mode::Normal if !self.message.is_empty() => self.message.clone(),
+            Mode::Normal if !self.filter.is_empty() => format!("/{}  (esc clears)  {}", self.filter, HELP),
+            Mode::Normal => HELP.to_string(),
+        };
+        queue!(out, MoveTo(0, rows.saturating_sub(1) as u16), Print(format_field(&status_line, cols, true)))?;
+        out.flush()?;
+        Ok(())
+    }
+
+    // save after a change, the message is also the commit message
+    fn save(&mut self, message: &str) {
+        let saved = self.profile
+                        .save_to_file(self.profile_name,
+                                      self.profile_folder,
+                                      self.key,
+                                      false,
+                                      false,
+                                      false,
+                                      &self.fingerprint)
+                        .and_then(|_| profile_paths(self.profile_name, self.profile_folder))
+                        .and_then(|(_, path)| profile_fingerprint(path))
+                        .and_then(|f| {
+                            self.fingerprint = f;
+                            git::commit(self.profile_name, self.profile_folder, message)
+                        });
+        self.message = match saved {
+            Ok(_) => message.to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+    }
+
+    fn set_status(&mut self, status: Status) {
+        if let Some(id) = self.current().map(|n| n.id) {
+            match self.profile.edit_note(id, &None, &None, &Some(status), false, ProfileFlags::default()) {
+                Ok(_) => self.save(&format!("edit note {}", id)),
+                Err(e) => self.message = format!("error: {}", e),
+            }
+        }
+    }
+
+    // handle a key, false when the tui should exit
+    fn key(&mut self, key: KeyEvent, screen: &mut Screen) -> Result<bool> {
+        let count = self.visible().len();
+        match self.mode {
+            Mode::Filter => match key.code {
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Normal;
+                }
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.selected = 0;
+                }
+                _ => {}
+            },
+            Mode::ConfirmDelete(ref children) => {
+                let ids = match (key.code, self.current().map(|n| n.id)) {
+                    (KeyCode::Char('y'), Some(id)) => vec![id],
+                    (KeyCode::Char('a'), Some(id)) if !children.is_empty() => [&[id], children.as_slice()].concat(),
+                    _ => vec![],
+                };
+                self.mode = Mode::Normal;
+                if !ids.is_empty() {
+                    self.profile.delete_note(&ids);
+                    self.save(&format!("delete note {}",
+                                       ids.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ")));
+                }
+            }
+            Mode::Transfer(ref mut target) => match key.code {
+                KeyCode::Esc => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    target.pop();
+                }
+                KeyCode::Char(c) => target.push(c),
+                KeyCode::Enter => {
+                    let target = target.clone();
+                    self.mode = Mode::Normal;
+                    if let Some(id) = self.current().map(|n| n.id) {
+                        let encrypted = self.profile.encrypted;
+                        let moved = self.profile.transfer_note(id,
+                                                               &target,
+                                                               self.profile_name,
+                                                               self.profile_folder,
+                                                               self.key,
+                                                               encrypted,
+                                                               false);
+                        match moved.and_then(|_| {
+                            git::commit(&target, self.profile_folder, &format!("transfer note from {}", self.profile_name))
+                        }) {
+                            Ok(_) => self.save(&format!("transfer note {} to {}", id, target)),
+                            Err(e) => self.message = format!("error: {}", e),
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Mode::Normal => {
+                self.message.clear();
+                let half = (terminal::size()?.1 as usize / 2).max(1);
+                match (key.code, key.modifiers.contains(KeyModifiers::CONTROL)) {
+                    (KeyCode::Char('c'), true) | (KeyCode::Char('q'), false) | (KeyCode::Esc, _)
+                        if self.filter.is_empty() => return Ok(false),
+                    (KeyCode::Esc, _) => self.filter.clear(),
+                    (KeyCode::Char('d'), true) => self.selected = (self.selected + half).min(count.saturating_sub(1)),
+                    (KeyCode::Char('u'), true) => self.selected = self.selected.saturating_sub(half),
+                    (KeyCode::Char('j'), _) | (KeyCode::Down, _) => {
+                        self.selected = (self.selected + 1).min(count.saturating_sub(1))
+                    }
+                    (KeyCode::Char('k'), _) | (KeyCode::Up, _) => self.selected = self.selected.saturating_sub(1),
+                    (KeyCode::Char('g'), _) | (KeyCode::Home, _) => self.selected = 0,
+                    (KeyCode::Char('G'), _) | (KeyCode::End, _) => self.selected = count.saturating_sub(1),
+                    (KeyCode::Char('/'), _) => self.mode = Mode::Filter,
+                    (KeyCode::Char('b'), _) => self.set_status(Status::Blank),
+                    (KeyCode::Char('s'), _) => self.set_status(Status::Started),
+                    (KeyCode::Char('u'), _) => self.set_status(Status::Urgent),
+                    (KeyCode::Char('c'), _) => self.set_status(Status::Done),
+                    (KeyCode::Char('D'), _) if count > 0 => {
+                        if let Some(id) = self.current().map(|n| n.id) {
+                            self.mode = Mode::ConfirmDelete(self.profile.descendants(&[id]));
+                        }
+                    }
+                    (KeyCode::Char('t'), _) if count > 0 => self.mode = Mode::Transfer(String::new()),
+                    (KeyCode::Char('e'), _) | (KeyCode::Enter, _) => {
+                        if let Some(id) = self.current().map(|n| n.id) {
+                            let flags = ProfileFlags {
+                                editor: true,
+                                encrypted: self.profile.encrypted,
+                                ..Default::default()
+                            };
+                            let profile = &mut *self.profile;
+                            // a refused editor warning or a failed editor shouldn't end the tui
+                            match screen.suspend(|| profile.edit_note(id, &None, &None, &None, false, flags))? {
+                                Ok(_) => self.save(&format!("edit note {}", id)),
+                                Err(e) => self.message = format!("error: {}", e),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// run the tui until the user quits
+pub fn run(profile: &mut Profile,
+           profile_name: &str,
+           profile_folder: &Option<String>,
+           key: Option<&String>,
+           fingerprint: &mut u64,
+           theme: &Theme,
+           color: bool)
+           -> Result<()> {
+    if !istty(STDIN_FILENO) || !istty(STDOUT_FILENO) {
+        return specific_fail_str!("the tui needs a terminal");
+    }
+    let mut tui = Tui {
+        profile,
+        profile_name,
+        profile_folder,
+        key,
+        fingerprint: *fingerprint,
+        theme: theme.clone(),
+        color,
+        filter: String::new(),
+        mode: Mode::Normal,
+        selected: 0,
+        offset: 0,
+        message: String::new(),
+    };
+    let mut screen = Screen::enter()?;
+    let result = event_loop(&mut tui, &mut screen);
+    *fingerprint = tui.fingerprint;
+    result
+}
+
+fn event_loop(tui: &mut Tui, screen: &mut Screen) -> Result<()> {
+    loop {
+        tui.draw(&mut screen.out)?;
+        if let Event::Key(k) = event::read()? {
+            if k.kind == KeyEventKind::Press && !tui.key(k, screen)? {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
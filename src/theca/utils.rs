@@ -3,14 +3,17 @@ use std::io::{Write, Read, stdout};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::env::{var};
-use std::iter::repeat;
 use std::time::UNIX_EPOCH;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::collections::HashSet;
 
 use crossterm::{
     style::{Attribute, SetAttribute},
-    execute,
+    queue,
     tty::IsTty,
 };
+use serde::Serialize;
 
 // tempfile imports
 use tempfile::Builder; // replacement for TempDir
@@ -20,20 +23,33 @@ use std::io::stdin;
 // theca imports
 use crate::{specific_fail, specific_fail_str};
 use crate::errors::{Result, Error, ErrorKind};
-use crate::lineformat::LineFormat;
-use crate::profile::{DATEFMT_SHORT, Profile, ProfileFlags}; // Import ProfileFlags
+use crate::profile::{DATEFMT, DATEFMT_SHORT, Profile, ProfileFlags}; // Import ProfileFlags
 use crate::item::{Item, Status};
 
 pub use libc::{STDIN_FILENO, STDOUT_FILENO, STDERR_FILENO};
 
 pub fn istty(fd: i32) -> bool {
-    // crossterm provides IsTty trait for stdout/stdin
+    // crossterm provides IsTty trait for stdout/stdin/stderr
     match fd {
+        STDIN_FILENO => stdin().is_tty(),
         STDOUT_FILENO => stdout().is_tty(),
-        _ => false // simplified
+        STDERR_FILENO => std::io::stderr().is_tty(),
+        _ => false,
     }
 }
 
+/// whether a file descriptor is a pipe or a regular file, input that ends
+/// on its own. a terminal, /dev/null or a socket left open by cron or a
+/// service manager isn't.
+pub fn is_piped(fd: i32) -> bool {
+    // SAFETY: fstat only writes into the zeroed stat it is given
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+        return false;
+    }
+    matches!(stat.st_mode & libc::S_IFMT, libc::S_IFIFO | libc::S_IFREG)
+}
+
 pub fn termsize() -> usize {
     if let Ok((cols, _rows)) = crossterm::terminal::size() {
         cols as usize
@@ -42,11 +58,61 @@ pub fn termsize() -> usize {
     }
 }
 
+/// the height of the terminal in lines, 0 when it can't be found
+pub fn termheight() -> usize {
+    if let Ok((_cols, rows)) = crossterm::terminal::size() {
+        rows as usize
+    } else {
+        0
+    }
+}
+
+/// the number of terminal lines `output` takes up, counting long lines
+/// that wrap
+pub fn output_height(output: &str, width: usize) -> usize {
+    output.lines()
+          .map(|l| {
+              let len = l.chars().count();
+              if width == 0 || len == 0 {
+                  1
+              } else {
+                  len.div_ceil(width)
+              }
+          })
+          .sum()
+}
+
+/// write `output` to stdout, through `$PAGER` (default `less -R`) when
+/// `pager` is set, stdout is a tty and the output is taller than the
+/// terminal. an empty `$PAGER` disables paging.
+pub fn page_output(output: &[u8], pager: bool) -> Result<()> {
+    let height = termheight();
+    let text = String::from_utf8_lossy(output);
+    if pager && istty(STDOUT_FILENO) && height > 0 && output_height(&text, termsize()) >= height {
+        let command = var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+        let mut words = command.split_whitespace();
+        if let Some(program) = words.next() {
+            if let Ok(mut child) = Command::new(program).args(words).stdin(Stdio::piped()).spawn() {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // the pager quitting early closes the pipe, that's fine
+                    let _ = stdin.write_all(output);
+                }
+                child.wait()?;
+                return Ok(());
+            }
+        }
+    }
+    let mut out = stdout().lock();
+    out.write_all(output)?;
+    out.flush()?;
+    Ok(())
+}
+
 pub fn extract_status(status_str: Option<String>) -> Result<Option<Status>> {
     match status_str.as_deref() {
         Some("started") | Some("Started") => Ok(Some(Status::Started)),
         Some("urgent") | Some("Urgent") => Ok(Some(Status::Urgent)),
-        Some("done") | Some("Done") => Ok(Some(Status::Urgent)),
+        Some("done") | Some("Done") => Ok(Some(Status::Done)),
         Some("blank") | Some("Blank") | Some("none") => Ok(Some(Status::Blank)),
         None => Ok(None),
         Some(_) => specific_fail_str!("Invalid status (started,urgent,done, or none)"),
@@ -86,7 +152,7 @@ pub fn drop_to_editor(contents: &str, id: Option<usize>, title: Option<&str>) ->
         
     // lets start `editor` and edit the file at `tmppath`
     let mut editor_command = Command::new(&editor);
-    editor_command.arg(&tmppath.display().to_string());
+    editor_command.arg(tmppath.display().to_string());
     editor_command.stdin(Stdio::inherit());
     editor_command.stdout(Stdio::inherit());
     editor_command.stderr(Stdio::inherit());
@@ -108,6 +174,16 @@ pub fn drop_to_editor(contents: &str, id: Option<usize>, title: Option<&str>) ->
     }
 }
 
+/// `text`, or what is read from stdin when it is `-`
+pub fn text_or_stdin(text: &str) -> Result<String> {
+    if text != "-" {
+        return Ok(text.to_string());
+    }
+    let mut buf = String::new();
+    stdin().read_to_string(&mut buf)?;
+    Ok(buf)
+}
+
 pub fn get_password() -> Result<String> {
     print!("Key: ");
     stdout().flush()?;
@@ -153,8 +229,8 @@ pub fn get_yn_input(message: &str) -> Result<bool> {
     stdout().flush()?;
     
     let stdin = stdin();
-    let yes = vec!["y", "Y", "yes", "YES", "Yes"];
-    let no = vec!["n", "N", "no", "NO", "No"];
+    let yes = ["y", "Y", "yes", "YES", "Yes"];
+    let no = ["n", "N", "no", "NO", "No"];
     
     loop {
         print!("[y/n]# ");
@@ -171,16 +247,15 @@ pub fn get_yn_input(message: &str) -> Result<bool> {
     }
 }
 
-pub fn pretty_line(bold: &str, plain: &str, tty: bool) -> Result<()> {
-    let mut stdout = stdout();
+pub fn pretty_line<W: Write>(output: &mut W, bold: &str, plain: &str, tty: bool) -> Result<()> {
     if tty {
-        execute!(stdout, SetAttribute(Attribute::Bold))?;
+        queue!(output, SetAttribute(Attribute::Bold))?;
     }
-    print!("{}", bold);
+    write!(output, "{}", bold)?;
     if tty {
-         execute!(stdout, SetAttribute(Attribute::Reset))?;
+        queue!(output, SetAttribute(Attribute::Reset))?;
     }
-    print!("{}", plain);
+    write!(output, "{}", plain)?;
     Ok(())
 }
 
@@ -203,85 +278,57 @@ pub fn sanitize_filename(name: &str) -> String {
         .to_string()
 }
 
-fn print_header(line_format: &LineFormat) -> Result<()> {
-    let mut stdout = stdout();
-    let column_seperator: String = repeat(' ')
-                                       .take(line_format.colsep)
-                                       .collect();
-    let header_seperator: String = repeat('-')
-                                       .take(line_format.line_width())
-                                       .collect();
-    let tty = istty(STDOUT_FILENO);
-    let status = if line_format.status_width == 0 {
-        "".to_string()
-    } else {
-        format_field(&"status".to_string(), line_format.status_width, false) + &*column_seperator
-    };
-    
-    if tty {
-        execute!(stdout, SetAttribute(Attribute::Bold))?;
-    }
-    print!(
-                "{1}{0}{2}{0}{3}{4}\n{5}\n",
-                column_seperator,
-                format_field(&"id".to_string(), line_format.id_width, false),
-                format_field(&"title".to_string(), line_format.title_width, false),
-                status,
-                format_field(&"last touched".to_string(),
-                             line_format.touched_width,
-                             false),
-                header_seperator);
-    if tty {
-        execute!(stdout, SetAttribute(Attribute::Reset))?;
-    }
-    Ok(())
-}
-
-pub fn sorted_print(notes: &mut Vec<Item>,
-                    limit: usize,
-                    flags: ProfileFlags,
-                    status: Option<Status>)
-                    -> Result<()> {
-    let condensed = flags.condensed;
-    let yaml = flags.yaml;
-    let datesort = flags.datesort;
-    let reverse = flags.reverse;
-    let search_body = flags.search_body;
-
+/// filter, sort and limit notes the way `list` and `search` show them
+pub fn sort_notes(notes: &mut Vec<Item>,
+                  limit: usize,
+                  flags: &ProfileFlags,
+                  status: Option<Status>) {
     if let Some(status) = status {
         notes.retain(|n| n.status == status);
     }
-    let limit = if limit != 0 && limit < notes.len() {
-        limit
-    } else {
-        notes.len()
-    };
-    
-    if datesort {
+
+    if !flags.sort.is_empty() {
+        notes.sort_by(|a, b| {
+            flags.sort.iter().fold(Ordering::Equal, |o, k| o.then_with(|| k.compare(a, b)))
+        });
+    } else if flags.datesort {
         notes.sort_by(|a, b| {
-             let a_tm = parse_last_touched(&a.last_touched).unwrap_or(chrono::Local::now());
-             let b_tm = parse_last_touched(&b.last_touched).unwrap_or(chrono::Local::now());
-             a_tm.cmp(&b_tm)
+             cmp_last_touched(&a.last_touched, &b.last_touched).unwrap_or(Ordering::Equal)
         });
     }
 
-    if reverse {
+    if flags.reverse {
         notes.reverse();
     }
 
-    if yaml {
-        println!("{}", serde_yaml::to_string(&notes[0..limit].to_vec()).unwrap())
-    } else {
-        let line_format = LineFormat::new(&notes[0..limit], condensed, search_body)?;
-        if !condensed && !yaml {
-            print_header(&line_format)?;
-        }
-        for n in notes[0..limit].iter() {
-            n.print(&line_format, search_body)?;
-        }
-    };
+    if flags.tree {
+        *notes = tree_order(notes);
+    }
 
-    Ok(())
+    if limit != 0 && limit < notes.len() {
+        notes.truncate(limit);
+    }
+}
+
+/// the notes with children following their parent, siblings keep their
+/// order. notes whose parent isn't among them are at the top level.
+pub fn tree_order(notes: &[Item]) -> Vec<Item> {
+    fn add_children(id: usize, notes: &[Item], ordered: &mut Vec<Item>) {
+        for n in notes.iter().filter(|n| n.parent == Some(id) && n.id != id) {
+            ordered.push(n.clone());
+            add_children(n.id, notes, ordered);
+        }
+    }
+    let ids: HashSet<usize> = notes.iter().map(|n| n.id).collect();
+    let mut ordered = Vec::with_capacity(notes.len());
+    for n in notes.iter().filter(|n| n.parent.is_none_or(|p| !ids.contains(&p))) {
+        ordered.push(n.clone());
+        add_children(n.id, notes, &mut ordered);
+    }
+    // notes whose parents loop, which `move` doesn't allow
+    let placed: HashSet<usize> = ordered.iter().map(|n| n.id).collect();
+    ordered.extend(notes.iter().filter(|n| !placed.contains(&n.id)).cloned());
+    ordered
 }
 
 pub fn find_profile_folder(profile_folder: &Option<String>) -> Result<PathBuf> {
@@ -309,13 +356,173 @@ pub fn find_profile_folder(profile_folder: &Option<String>) -> Result<PathBuf> {
     }
 }
 
-pub fn parse_last_touched(lt: &str) -> Result<chrono::DateTime<chrono::Local>> {
-    lt.parse::<chrono::DateTime<chrono::Local>>().map_err(Error::from)
+/// returns the (directory, profile.yaml path) pair for a profile, the
+/// default profile lives directly in the profile folder
+pub fn profile_paths(profile_name: &str, profile_folder: &Option<String>) -> Result<(PathBuf, PathBuf)> {
+    let profile_base = find_profile_folder(profile_folder)?;
+    if profile_name == "default" {
+        Ok((profile_base.clone(), profile_base.join("profile.yaml")))
+    } else {
+        let dir = profile_base.join(profile_name);
+        let path = dir.join("profile.yaml");
+        Ok((dir, path))
+    }
+}
+
+/// parse a stored timestamp, either `%F %T %z` or RFC 3339
+pub fn parse_last_touched(lt: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    lt.parse::<chrono::DateTime<chrono::FixedOffset>>()
+      .map(|t| t.with_timezone(&chrono::Utc))
+      .map_err(Error::from)
+}
+
+/// the current time as a timestamp for storing in a profile
+pub fn now_timestamp() -> String {
+    chrono::Utc::now().format(DATEFMT).to_string()
+}
+
+/// rewrite a stored timestamp in UTC, timestamps that can't be parsed are
+/// left alone
+pub fn normalize_timestamp(lt: &str) -> String {
+    match parse_last_touched(lt) {
+        Ok(t) => t.format(DATEFMT).to_string(),
+        Err(_) => lt.to_string(),
+    }
+}
+
+/// a due date given on the command line as a timestamp to store. it is a
+/// full timestamp, or `YYYY-MM-DD` and `YYYY-MM-DD HH:MM` in local time.
+/// `none` is no due date.
+pub fn parse_due(s: &str) -> Result<Option<String>> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    if let Ok(t) = parse_last_touched(s) {
+        return Ok(Some(t.format(DATEFMT).to_string()));
+    }
+    let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+                    .or_else(|_| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+                    .or_else(|_| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_time(chrono::NaiveTime::MIN)));
+    match naive.ok().and_then(|t| t.and_local_timezone(chrono::Local).earliest()) {
+        Some(t) => Ok(Some(t.with_timezone(&chrono::Utc).format(DATEFMT).to_string())),
+        None => specific_fail!(format!("invalid due date '{}', use YYYY-MM-DD, 'YYYY-MM-DD HH:MM' or none", s)),
+    }
+}
+
+pub fn cmp_last_touched(a: &str, b: &str) -> Result<Ordering> {
+    let a_tm = parse_last_touched(a)?;
+    let b_tm = parse_last_touched(b)?;
+    Ok(a_tm.cmp(&b_tm))
 }
 
 pub fn localize_last_touched_string(lt: &str) -> Result<String> {
-    let t = parse_last_touched(lt)?;
-    Ok(t.format(DATEFMT_SHORT).to_string())
+    DateDisplay::default().format(lt)
+}
+
+/// The time zone dates are shown in
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DisplayTz {
+    #[default]
+    Local,
+    Named(chrono_tz::Tz),
+    Fixed(chrono::FixedOffset),
+}
+
+impl FromStr for DisplayTz {
+    type Err = Error;
+
+    /// `local`, `utc`, an IANA name like `Europe/Berlin` or an offset like
+    /// `+02:00`
+    fn from_str(s: &str) -> Result<DisplayTz> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(DisplayTz::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return Ok(DisplayTz::Named(chrono_tz::UTC));
+        }
+        if let Ok(tz) = s.parse::<chrono_tz::Tz>() {
+            return Ok(DisplayTz::Named(tz));
+        }
+        match s.parse::<chrono::FixedOffset>() {
+            Ok(offset) => Ok(DisplayTz::Fixed(offset)),
+            Err(_) => Err(format!("unknown time zone '{}'", s).into()),
+        }
+    }
+}
+
+/// How dates are rendered: in which time zone and whether as absolute or
+/// relative ("3h ago") times
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DateDisplay {
+    pub tz: DisplayTz,
+    pub relative: bool,
+}
+
+impl DateDisplay {
+    fn in_tz(&self, t: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::FixedOffset> {
+        match self.tz {
+            DisplayTz::Local => t.with_timezone(&chrono::Local).fixed_offset(),
+            DisplayTz::Named(tz) => t.with_timezone(&tz).fixed_offset(),
+            DisplayTz::Fixed(offset) => t.with_timezone(&offset),
+        }
+    }
+
+    /// render a stored timestamp for people
+    pub fn format(&self, lt: &str) -> Result<String> {
+        if self.relative {
+            self.relative(lt)
+        } else {
+            self.absolute(lt, DATEFMT_SHORT)
+        }
+    }
+
+    /// render a stored timestamp with a strftime format, ignoring
+    /// relative mode
+    pub fn absolute(&self, lt: &str, fmt: &str) -> Result<String> {
+        Ok(self.in_tz(parse_last_touched(lt)?).format(fmt).to_string())
+    }
+
+    /// render a stored timestamp relative to now
+    pub fn relative(&self, lt: &str) -> Result<String> {
+        let t = self.in_tz(parse_last_touched(lt)?);
+        Ok(relative_time(t, self.in_tz(chrono::Utc::now())))
+    }
+
+    /// render a stored timestamp as RFC 3339, for machine readable output
+    pub fn rfc3339(&self, lt: &str) -> Result<String> {
+        Ok(self.in_tz(parse_last_touched(lt)?).to_rfc3339())
+    }
+}
+
+/// describe `t` relative to `now`, e.g. "3h ago", "yesterday" or "in 2 days"
+pub fn relative_time<Tz: chrono::TimeZone>(t: chrono::DateTime<Tz>, now: chrono::DateTime<Tz>) -> String {
+    let secs = now.clone().signed_duration_since(t.clone()).num_seconds();
+    let future = secs < 0;
+    let secs = secs.abs();
+    let days = (now.date_naive() - t.date_naive()).num_days().abs();
+    let amount = if secs < 60 {
+        return "just now".to_string();
+    } else if secs < 60 * 60 {
+        format!("{}m", secs / 60)
+    } else if secs < 24 * 60 * 60 && days == 0 {
+        format!("{}h", secs / (60 * 60))
+    } else if days <= 1 {
+        return if future { "tomorrow" } else { "yesterday" }.to_string();
+    } else if days < 31 {
+        format!("{} days", days)
+    } else if days < 365 {
+        let months = days / 30;
+        format!("{} month{}", months, if months == 1 { "" } else { "s" })
+    } else {
+        let years = days / 365;
+        format!("{} year{}", years, if years == 1 { "" } else { "s" })
+    };
+    if future {
+        format!("in {}", amount)
+    } else {
+        format!("{} ago", amount)
+    }
 }
 
 pub fn validate_profile_from_path(profile_path: &PathBuf) -> (bool, bool) {
@@ -351,43 +558,48 @@ pub fn validate_profile_from_path(profile_path: &PathBuf) -> (bool, bool) {
     (false, false)
 }
 
-pub fn path_to_profile_name(profile_path: &PathBuf) -> Result<String> {
+pub fn path_to_profile_name(profile_path: &Path) -> Result<String> {
     let just_f = profile_path.file_stem().unwrap();
     Ok(just_f.to_str().unwrap().to_string())
 }
 
-pub fn profiles_in_folder(folder: &Path) -> Result<()> {
+/// A profile found in a profile folder
+#[derive(Serialize, Clone, Debug)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub encrypted: bool,
+}
+
+pub fn profiles_in_folder(folder: &Path) -> Result<Vec<ProfileInfo>> {
+    let mut profiles = vec![];
     if folder.is_dir() {
-        println!("# profiles in {}", folder.display());
-        
         // Check for special 'default' profile in root
         let root_profile = folder.join("profile.yaml");
         let is_root_prof = validate_profile_from_path(&root_profile);
         if is_root_prof.0 {
-            let mut msg = "default".to_string();
-            if is_root_prof.1 {
-                msg = format!("{} [encrypted]", msg);
-            }
-            println!("    {}", msg);
+            profiles.push(ProfileInfo {
+                name: "default".to_string(),
+                encrypted: is_root_prof.1,
+            });
         }
 
-        for entry in read_dir(folder)? {
-            let entry = entry?;
+        let mut entries = read_dir(folder)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
             let path = entry.path();
             if path.is_dir() {
                 let profile_yaml = path.join("profile.yaml");
                 let is_prof = validate_profile_from_path(&profile_yaml);
                 if is_prof.0 {
-                    let mut msg = path_to_profile_name(&path)?; // path is the dir, stem is dir name
-                    if is_prof.1 {
-                        msg = format!("{} [encrypted]", msg);
-                    }
-                    println!("    {}", msg);
+                    profiles.push(ProfileInfo {
+                        name: path_to_profile_name(&path)?, // path is the dir, stem is dir name
+                        encrypted: is_prof.1,
+                    });
                 }
             }
         }
     }
-    Ok(())
+    Ok(profiles)
 }
 
 pub fn profile_fingerprint<P: AsRef<Path>>(path: P) -> Result<u64> {
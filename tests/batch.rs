@@ -0,0 +1,64 @@
+extern crate theca;
+
+use clap::Parser;
+use theca::args::{Cli, Commands};
+use theca::batch::{self, parse};
+use theca::config::Config;
+use theca::{Profile, Session};
+
+#[test]
+fn test_batch_parse() {
+    let cli = Cli::parse_from(["theca", "--profile", "work"]);
+    let text = "# notes for today\n\
+                add \"call back\" -s urgent\n\
+                \n\
+                {\"op\": \"edit\", \"id\": 3, \"status\": \"done\"}\n\
+                del 4 5\n";
+    let commands = parse(&cli, text).ok().unwrap();
+    assert_eq!(commands.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![2, 4, 5]);
+    assert!(commands.iter().all(|(_, c)| c.profile == "work"));
+    assert!(matches!(commands[0].1.command,
+                     Some(Commands::Add { ref title, ref status, .. })
+                         if title == "call back" && status.as_deref() == Some("urgent")));
+    assert!(matches!(commands[1].1.command, Some(Commands::Edit { id: 3, ref status, .. }) if status.is_some()));
+    assert!(matches!(commands[2].1.command, Some(Commands::Del { ref id, .. }) if id == &vec![4, 5]));
+
+    // nothing runs when any line is wrong
+    for bad in ["list\nedit\n", "list\n{\"op\": \"del\"}\n", "list\nshell\n"] {
+        let e = parse(&cli, bad).err().unwrap();
+        assert!(e.to_string().contains("line 2"), "{}", e);
+    }
+}
+
+#[test]
+fn test_batch_clear_needs_yes() {
+    let cli = Cli::parse_from(["theca"]);
+    let e = parse(&cli, "list\nclear\n").err().unwrap();
+    assert!(e.to_string().contains("line 2: clear needs --yes"), "{}", e);
+    assert!(parse(&cli, "--yes clear\n").is_ok());
+    assert!(parse(&Cli::parse_from(["theca", "--yes"]), "clear\n").is_ok());
+}
+
+#[test]
+fn test_atomic_batch_writes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = Some(dir.path().display().to_string());
+    Profile { encrypted: false, notes: vec![] }.save_to_file("default", &folder, None, true, false, true, &0).unwrap();
+    let cli = Cli::parse_from(["theca", "--profile-folder", folder.as_deref().unwrap()]);
+
+    // the failing edit comes after lines that would write files right away
+    for writes in ["view save urgent --status urgent", "sync", "export --format csv notes.csv"] {
+        let path = dir.path().join("batch.txt");
+        std::fs::write(&path, format!("add \"a note\"\n{}\nedit 99 --title nope\n", writes)).unwrap();
+        let (profile, fingerprint) = Profile::new("default", &folder, None, false, false, true).unwrap();
+        let session = Session { profile, fingerprint, autosave: false, unsaved: vec![] };
+        let e = batch::run(&cli, session, path.to_str().unwrap(), true).err().unwrap();
+        assert!(e.to_string().contains("line 2"), "{}", e);
+    }
+    let config = Config::load(&Config::profile_path("default", &folder).unwrap()).unwrap();
+    assert!(config.views.is_empty());
+    let (profile, _) = Profile::new("default", &folder, None, false, false, true).unwrap();
+    assert!(profile.notes.is_empty());
+    let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().flatten().map(|e| e.file_name()).collect();
+    assert!(!files.iter().any(|f| f.to_string_lossy().ends_with(".md") || f == "notes.csv"), "{:?}", files);
+}
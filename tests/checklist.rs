@@ -0,0 +1,37 @@
+extern crate theca;
+
+use theca::checklist::{auto_status, progress, set_checked, tasks};
+use theca::item::{Field, Item, Status};
+
+static BODY: &str = "# todo\n- [ ] one\n- [x] two\n```\n- [ ] in code\n```\n  * [X] three\n1. [ ] four\n";
+
+#[test]
+fn test_checklist_items() {
+    let t = tasks(BODY);
+    assert_eq!(t.iter().map(|t| (t.line, t.checked, t.text.as_str())).collect::<Vec<_>>(),
+               vec![(1, false, "one"), (2, true, "two"), (6, true, "three"), (7, false, "four")]);
+    assert_eq!(progress(BODY), Some((2, 4)));
+    assert_eq!(progress("no list\n- plain item"), None);
+
+    let note = Item { body: BODY.to_string(), ..Default::default() };
+    assert_eq!(note.field(Field::Progress), "2/4");
+}
+
+#[test]
+fn test_checklist_set_checked() {
+    let body = set_checked(BODY, 1, true).ok().unwrap();
+    let body = set_checked(&body, 3, false).ok().unwrap();
+    assert_eq!(body, "# todo\n- [x] one\n- [x] two\n```\n- [ ] in code\n```\n  * [ ] three\n1. [ ] four\n");
+    assert!(set_checked(BODY, 0, true).is_err());
+    assert!(set_checked(BODY, 5, true).is_err());
+}
+
+#[test]
+fn test_checklist_auto_status() {
+    assert_eq!(auto_status(Status::Blank, 0, 3), Status::Blank);
+    assert_eq!(auto_status(Status::Blank, 1, 3), Status::Started);
+    assert_eq!(auto_status(Status::Urgent, 1, 3), Status::Urgent);
+    assert_eq!(auto_status(Status::Urgent, 3, 3), Status::Done);
+    // unchecking a done note reopens it
+    assert_eq!(auto_status(Status::Done, 2, 3), Status::Started);
+}
@@ -0,0 +1,47 @@
+extern crate theca;
+
+use theca::config::{Config, View};
+use theca::item::Status;
+
+#[test]
+fn test_load_missing_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config::load(&dir.path().join("config.yaml"));
+    assert!(config.is_ok());
+    assert!(config.ok().unwrap().views.is_empty());
+}
+
+#[test]
+fn test_save_and_load_view() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("config.yaml");
+    let view = View {
+        search: Some("bug".to_string()),
+        status: Some(Status::Urgent),
+        datesort: true,
+        limit: 5,
+        ..Default::default()
+    };
+    let mut config = Config::default();
+    config.views.insert("urgent-work".to_string(), view.clone());
+    assert!(config.save(&path).is_ok());
+
+    let loaded = Config::load(&path).ok().unwrap();
+    assert_eq!(loaded.views.get("urgent-work"), Some(&view));
+    assert_eq!(view.to_string(),
+               "--search \"bug\" --status urgent --datesort --limit 5".to_string());
+}
+
+#[test]
+fn test_merge_prefers_profile_views() {
+    let mut user = Config::default();
+    user.views.insert("a".to_string(), View { limit: 1, ..Default::default() });
+    user.views.insert("b".to_string(), View { limit: 2, ..Default::default() });
+    let mut profile = Config::default();
+    profile.views.insert("a".to_string(), View { limit: 3, ..Default::default() });
+
+    user.merge(profile);
+    assert_eq!(user.views.len(), 2);
+    assert_eq!(user.views["a"].limit, 3);
+    assert_eq!(user.views["b"].limit, 2);
+}
@@ -0,0 +1,110 @@
+extern crate theca;
+
+use theca::Profile;
+use theca::export::{escape_html, export_html, export_table, import_table, markdown_to_html};
+use theca::item::{Field, Item, Status};
+use theca::utils::{DateDisplay, DisplayTz};
+
+fn note(id: usize, title: &str, body: &str, status: Status) -> Item {
+    Item {
+        id,
+        title: title.to_string(),
+        body: body.to_string(),
+        status,
+        last_touched: "2015-01-22 19:43:24 -0800".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_export_quotes_multiline_bodies() {
+    let notes = vec![note(1, "a, title", "line one\nline \"two\"", Status::Started)];
+    let mut out: Vec<u8> = vec![];
+    assert!(export_table(&mut out, &notes, &Field::all(), true, false).is_ok());
+    assert_eq!(String::from_utf8(out).unwrap(),
+               "id,title,status,body,touched,created,due,tags\n\
+                1,\"a, title\",Started,\"line one\nline \"\"two\"\"\",2015-01-22 19:43:24 -0800,,,\n"
+                   .to_string());
+}
+
+#[test]
+fn test_import_round_trip() {
+    let mut notes = vec![note(1, "first", "a\nbody", Status::Urgent),
+                         note(2, "second", "", Status::Blank)];
+    notes[0].created = Some("2015-01-20 08:00:00 -0800".to_string());
+    notes[0].due = Some("2015-02-01 00:00:00 +0000".to_string());
+    notes[0].tags = vec!["work".to_string(), "phone".to_string()];
+    let mut out: Vec<u8> = vec![];
+    assert!(export_table(&mut out, &notes, &Field::all(), true, true).is_ok());
+
+    let mut p = Profile {
+        encrypted: false,
+        notes: vec![],
+    };
+    let report = import_table(&mut p, &out[..], None, true).ok().unwrap();
+    assert_eq!(report.imported, vec![1, 2]);
+    assert!(report.errors.is_empty());
+    assert_eq!(p.notes[0].title, "first".to_string());
+    assert_eq!(p.notes[0].body, "a\nbody".to_string());
+    assert_eq!(p.notes[0].status, Status::Urgent);
+    assert_eq!(p.notes[0].last_touched, "2015-01-22 19:43:24 -0800".to_string());
+    assert_eq!(p.notes[0].created.as_deref(), Some("2015-01-20 08:00:00 -0800"));
+    assert_eq!(p.notes[0].due.as_deref(), Some("2015-02-01 00:00:00 +0000"));
+    assert_eq!(p.notes[0].tags, vec!["work", "phone"]);
+    assert_eq!(p.notes[1].due, None);
+    assert_eq!(p.notes[1].status, Status::Blank);
+}
+
+#[test]
+fn test_import_reports_bad_rows() {
+    let input = "no title here,started\nok,urgent\n,done\nbad,nope\n";
+    let mut p = Profile {
+        encrypted: false,
+        notes: vec![],
+    };
+    let columns = [Field::Title, Field::Status];
+    let report = import_table(&mut p, input.as_bytes(), Some(&columns), false).ok().unwrap();
+    assert_eq!(report.imported, vec![1, 2]);
+    assert_eq!(report.errors.len(), 2);
+    assert_eq!(report.errors[0].0, 3);
+    assert_eq!(report.errors[1], (4, "invalid status 'nope'".to_string()));
+}
+
+#[test]
+fn test_markdown_to_html_escapes_raw_html() {
+    assert_eq!(markdown_to_html("*hi* <b>there</b>"),
+               "<p><em>hi</em> &lt;b&gt;there&lt;/b&gt;</p>\n".to_string());
+    assert_eq!(escape_html("a < \"b\" & 'c'"), "a &lt; &quot;b&quot; &amp; &#39;c&#39;".to_string());
+}
+
+#[test]
+fn test_markdown_to_html_drops_unsafe_links() {
+    assert_eq!(markdown_to_html("[x](javascript:alert(1))"),
+               "<p><a href=\"#\">x</a></p>\n".to_string());
+    assert_eq!(markdown_to_html("![x]( JavaScript:alert(1))"),
+               "<p><img src=\"#\" alt=\"x\" /></p>\n".to_string());
+    assert_eq!(markdown_to_html("[x](data:text/html,hi)"),
+               "<p><a href=\"#\">x</a></p>\n".to_string());
+    assert_eq!(markdown_to_html("[a](https://example.com) [b](mailto:me@example.com) [c](notes/2.html)"),
+               "<p><a href=\"https://example.com\">a</a> <a href=\"mailto:me@example.com\">b</a> \
+                <a href=\"notes/2.html\">c</a></p>\n".to_string());
+}
+
+#[test]
+fn test_export_html_site() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = vec![note(1, "first <note>", "# body", Status::Urgent),
+                     note(2, "second", "", Status::Blank)];
+    let dates = DateDisplay { tz: DisplayTz::Fixed(chrono::FixedOffset::east_opt(0).unwrap()), relative: false };
+    assert!(export_html(dir.path(), "default", &notes, &dates).is_ok());
+    for f in ["index.html", "style.css", "search.js", "search-index.js", "notes/1.html",
+              "notes/2.html"].iter() {
+        assert!(dir.path().join(f).is_file());
+    }
+    let index = std::fs::read_to_string(dir.path().join("index.html")).unwrap();
+    assert!(index.contains("<a href=\"notes/1.html\">first &lt;note&gt;</a>"));
+    assert!(index.contains("<span class=\"badge urgent\">Urgent</span>"));
+    assert!(index.contains("<td>2015-01-23 03:43:24</td>"));
+    let page = std::fs::read_to_string(dir.path().join("notes/1.html")).unwrap();
+    assert!(page.contains("<h1>body</h1>"));
+}
@@ -0,0 +1,44 @@
+extern crate theca;
+
+use theca::frontmatter::{parse, render, strip, Frontmatter};
+use theca::item::{Item, Status};
+
+#[test]
+fn test_frontmatter_round_trip() {
+    let note = Item {
+        id: 3,
+        title: "  key: value # not a comment".to_string(),
+        status: Status::Urgent,
+        body: "body\n---\nwith a rule\n".to_string(),
+        last_touched: "2015-01-22 19:43:24 +0000".to_string(),
+        created: Some("2015-01-20 10:00:00 +0000".to_string()),
+        due: Some("2015-02-01 09:00:00 +0000".to_string()),
+        tags: vec!["work".to_string(), "a: b".to_string()],
+        parent: Some(1),
+    };
+    let content = render(&Frontmatter::from_item(&note), &note.body).ok().unwrap();
+    let (fm, body) = parse(&content).unwrap();
+    assert_eq!(fm, Frontmatter::from_item(&note));
+    assert_eq!(fm.status().ok(), Some(Status::Urgent));
+    assert_eq!(body, note.body);
+}
+
+#[test]
+fn test_frontmatter_keeps_unknown_keys() {
+    let content = "---\nid: 4\ntitle: x\nstatus: Started\nproject: apollo\nreviewers: [ann, bo]\n---\nbody";
+    let (fm, body) = parse(content).unwrap();
+    assert_eq!(fm.id, Some(4));
+    assert_eq!(fm.status().ok(), Some(Status::Started));
+    assert_eq!(fm.extra.len(), 2);
+    assert_eq!(body, "body".to_string());
+    assert!(render(&fm, &body).ok().unwrap().contains("project: apollo\n"));
+}
+
+#[test]
+fn test_invalid_frontmatter() {
+    let content = "---\nid: [unclosed\n---\nbody";
+    assert!(parse(content).is_none());
+    assert_eq!(strip(content), "body".to_string());
+    assert!(parse("no frontmatter").is_none());
+    assert_eq!(strip("no frontmatter"), "no frontmatter".to_string());
+}
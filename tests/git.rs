@@ -0,0 +1,94 @@
+extern crate theca;
+
+use std::path::Path;
+use std::process::Command;
+
+use theca::git::{self, merge_notes, Pulled};
+use theca::item::{Item, Status};
+use theca::Profile;
+
+fn note(id: usize, title: &str, touched: &str) -> Item {
+    Item {
+        id,
+        title: title.to_string(),
+        last_touched: format!("2015-01-22 {} +0000", touched),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_merge_notes_by_id() {
+    let base = vec![note(1, "a", "10:00:00"), note(2, "b", "10:00:00"), note(3, "c", "10:00:00")];
+    let mut ours = base.clone();
+    ours[0].status = Status::Done;
+    ours[1].title = "b ours".to_string();
+    ours.push(note(4, "new here", "11:00:00"));
+    let mut theirs = base.clone();
+    theirs[0].body = "remote body".to_string();
+    theirs[1].title = "b theirs".to_string();
+    theirs[1].last_touched = "2015-01-22 12:00:00 +0000".to_string();
+    theirs.remove(2);
+    theirs.push(note(4, "new there", "11:00:00"));
+
+    let (merged, conflicts) = merge_notes(&base, &ours, &theirs);
+    let titles: Vec<(usize, &str)> = merged.iter().map(|n| (n.id, n.title.as_str())).collect();
+    assert_eq!(titles, vec![(1, "a"), (2, "b theirs"), (4, "new here"), (5, "new there")]);
+    assert_eq!(merged[0].status, Status::Done);
+    assert_eq!(merged[0].body, "remote body");
+    assert_eq!(conflicts.len(), 2);
+}
+
+// git with an identity to commit as, the tests don't depend on the user's
+// git config
+fn git_command() -> Command {
+    let mut command = Command::new("git");
+    command.envs([("GIT_AUTHOR_NAME", "theca"), ("GIT_AUTHOR_EMAIL", "theca@example.com"),
+                  ("GIT_COMMITTER_NAME", "theca"), ("GIT_COMMITTER_EMAIL", "theca@example.com")]);
+    command
+}
+
+// set up the identity in a repository so the commits theca makes there work
+fn set_identity(repo: &Path) {
+    for (k, v) in [("user.name", "theca"), ("user.email", "theca@example.com")] {
+        assert!(git_command().arg("-C").arg(repo).args(["config", k, v]).status().unwrap().success());
+    }
+}
+
+#[test]
+fn test_git_push_and_pull() {
+    let dir = tempfile::tempdir().unwrap();
+    let remote = dir.path().join("remote.git");
+    assert!(git_command().args(["init", "--quiet", "--bare"]).arg(&remote).status().unwrap().success());
+    let (a, b) = (Some(dir.path().join("a").display().to_string()), Some(dir.path().join("b").display().to_string()));
+
+    let mut profile = Profile { encrypted: false, notes: vec![] };
+    profile.add_note("first", &[], None, false, false).unwrap();
+    profile.save_to_file("work", &a, None, true, false, true, &0).unwrap();
+    let ours = dir.path().join("a").join("work");
+    assert!(git_command().args(["init", "--quiet"]).arg(&ours).status().unwrap().success());
+    set_identity(&ours);
+    assert_eq!(git::init("work", &a, &Some(remote.display().to_string())).unwrap(), None);
+    git::push("work", &a).unwrap();
+
+    let clone = dir.path().join("b").join("work");
+    assert!(git_command().args(["clone", "--quiet"]).arg(&remote).arg(&clone).status().unwrap().success());
+    set_identity(&clone);
+    let (mut theirs, fingerprint) = Profile::new("work", &b, None, false, false, true).unwrap();
+    theirs.add_note("second", &[], None, false, false).unwrap();
+    theirs.save_to_file("work", &b, None, false, false, true, &fingerprint).unwrap();
+    git::commit("work", &b, "add note 2").unwrap();
+    git::push("work", &b).unwrap();
+
+    profile.edit_note(1, &Some("first edited".to_string()), &None, &None, false, Default::default()).unwrap();
+    profile.save_to_file("work", &a, None, false, false, true, &0).unwrap();
+    git::commit("work", &a, "edit note 1").unwrap();
+    match git::pull("work", &a, &profile, None).unwrap() {
+        Pulled::Merged { notes, conflicts } => {
+            let titles: Vec<&str> = notes.iter().map(|n| n.title.as_str()).collect();
+            assert_eq!(titles, vec!["first edited", "second"]);
+            assert!(conflicts.is_empty());
+        }
+        _ => panic!("expected a merge"),
+    }
+    assert!(git::log("work", &a, None).unwrap().contains("edit note 1"));
+}
@@ -0,0 +1,48 @@
+extern crate theca;
+
+use theca::graph::{Edge, Graph, GraphFormat};
+use theca::item::{Item, Status};
+
+fn note(id: usize, status: Status, body: &str, tags: &[&str], parent: Option<usize>) -> Item {
+    Item {
+        id,
+        title: format!("note \"{}\"", id),
+        status,
+        body: body.to_string(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        parent,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_graph_edges() {
+    let notes = vec![note(1, Status::Urgent, "[[2]] [[2]] [[3]] [[1]]", &["work", "home"], None),
+                     note(2, Status::Done, "", &["work"], Some(1)),
+                     note(3, Status::Blank, "[[nope]]", &["home"], Some(2))];
+    // note 3 is filtered out, the edges to it are left out
+    let graph = Graph::new(&notes[..2], &notes);
+    assert_eq!(graph.tags, vec!["work"]);
+    assert_eq!(graph.edges,
+               vec![Edge::Link { from: 1, to: 2 },
+                    Edge::Parent { child: 2, parent: 1 },
+                    Edge::Tag { note: 1, tag: 0 },
+                    Edge::Tag { note: 2, tag: 0 }]);
+}
+
+#[test]
+fn test_graph_formats() {
+    let notes = vec![note(1, Status::Urgent, "[[2]]", &[], None), note(2, Status::Blank, "", &[], Some(1))];
+    let graph = Graph::new(&notes, &notes);
+
+    let dot = graph.render(GraphFormat::Dot, "default").ok().unwrap();
+    assert!(dot.starts_with("digraph \"default\" {\n"));
+    assert!(dot.contains("    n1 [label=\"1: note \\\"1\\\"\\nUrgent\", fillcolor=\"#ffb3b3\"];\n"));
+    assert!(dot.contains("    n1 -> n2;\n"));
+    assert!(dot.contains("    n2 -> n1 [style=bold, label=\"part of\"];\n"));
+
+    let mermaid = graph.render(GraphFormat::Mermaid, "default").ok().unwrap();
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("    n2[\"2: note #quot;2#quot;\"]\n"));
+    assert!(mermaid.contains("    class n1 urgent\n"));
+}
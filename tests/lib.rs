@@ -1,8 +1,28 @@
 extern crate theca;
 
-use theca::{Profile, BoolFlags};
+use clap::Parser;
+use tempfile::TempDir;
+use theca::{execute, Profile, ProfileFlags, Session};
+use theca::args::Cli;
+use theca::errors::Result;
 use theca::item::Status;
 
+// a session on an empty default profile in a new temporary folder
+fn temp_session() -> (TempDir, Session) {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = Some(dir.path().display().to_string());
+    Profile { encrypted: false, notes: vec![] }.save_to_file("default", &folder, None, true, false, true, &0).unwrap();
+    let (profile, fingerprint) = Profile::new("default", &folder, None, false, false, true).unwrap();
+    (dir, Session { profile, fingerprint, autosave: false, unsaved: vec![] })
+}
+
+// run `args` like a theca command line against the session in `dir`
+fn run_in(dir: &TempDir, session: &mut Session, args: &[&str]) -> Result<String> {
+    let folder = dir.path().display().to_string();
+    let cli = Cli::parse_from(["theca", "--profile-folder", &folder].iter().chain(args));
+    execute(&cli, session).map(|out| String::from_utf8(out).unwrap())
+}
+
 #[test]
 fn test_add_note() {
     let mut p = Profile {
@@ -13,7 +33,6 @@ fn test_add_note() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -33,7 +52,6 @@ fn test_add_started_note() {
                        &[],
                        Some(Status::Started),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -53,7 +71,6 @@ fn test_add_urgent_note() {
                        &[],
                        Some(Status::Urgent),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -73,7 +90,6 @@ fn test_add_basic_body_note() {
                        &["and what?".to_string()],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -93,7 +109,6 @@ fn test_add_full_basic_body_note() {
                        &["and what?".to_string()],
                        Some(Status::Urgent),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -113,16 +128,15 @@ fn test_edit_note_title() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
     assert!(p.edit_note(1,
-                        &"this is a new title".to_string(),
-                        &[],
-                        Some(Status::Blank),
+                        &Some("this is a new title".to_string()),
+                        &None,
+                        &Some(Status::Blank),
                         false,
-                        BoolFlags::default())
+                        ProfileFlags::default())
              .is_ok());
     assert_eq!(p.notes[0].id, 1);
     assert_eq!(p.notes[0].title, "this is a new title".to_string());
@@ -140,38 +154,37 @@ fn test_edit_note_status() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
     assert!(p.edit_note(1,
-                        &"".to_string(),
-                        &[],
-                        Some(Status::Started),
+                        &None,
+                        &None,
+                        &Some(Status::Started),
                         false,
-                        BoolFlags::default())
+                        ProfileFlags::default())
              .is_ok());
     assert_eq!(p.notes[0].id, 1);
     assert_eq!(p.notes[0].title, "this is a title".to_string());
     assert_eq!(p.notes[0].status, Status::Started);
     assert_eq!(p.notes[0].body, "".to_string());
     assert!(p.edit_note(1,
-                        &"".to_string(),
-                        &[],
-                        Some(Status::Urgent),
+                        &None,
+                        &None,
+                        &Some(Status::Urgent),
                         false,
-                        BoolFlags::default())
+                        ProfileFlags::default())
              .is_ok());
     assert_eq!(p.notes[0].id, 1);
     assert_eq!(p.notes[0].title, "this is a title".to_string());
     assert_eq!(p.notes[0].status, Status::Urgent);
     assert_eq!(p.notes[0].body, "".to_string());
     assert!(p.edit_note(1,
-                        &"".to_string(),
-                        &[],
-                        Some(Status::Blank),
+                        &None,
+                        &None,
+                        &Some(Status::Blank),
                         false,
-                        BoolFlags::default())
+                        ProfileFlags::default())
              .is_ok());
     assert_eq!(p.notes[0].id, 1);
     assert_eq!(p.notes[0].title, "this is a title".to_string());
@@ -185,19 +198,18 @@ fn test_edit_note_body_basic() {
         encrypted: false,
         notes: vec![],
     };
-    let flags = BoolFlags::default();
+    let flags = ProfileFlags::default();
     assert!(p.add_note("this is a title",
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
     assert!(p.edit_note(1,
-                        &"".to_string(),
-                        &["woo body".to_string()],
-                        Some(Status::Blank),
+                        &None,
+                        &Some("woo body".to_string()),
+                        &Some(Status::Blank),
                         false,
                         flags)
              .is_ok());
@@ -207,6 +219,28 @@ fn test_edit_note_body_basic() {
     assert_eq!(p.notes[0].body, "woo body".to_string());
 }
 
+#[test]
+fn test_edit_note_body_append() {
+    let mut p = Profile {
+        encrypted: false,
+        notes: vec![],
+    };
+    assert!(p.add_note("this is a title",
+                       &["first line".to_string()],
+                       Some(Status::Blank),
+                       false,
+                       false)
+             .is_ok());
+    let flags = ProfileFlags { append: true, ..Default::default() };
+    assert!(p.edit_note(1, &None, &Some("second line".to_string()), &None, false, flags.clone()).is_ok());
+    assert_eq!(p.notes[0].body, "first line\nsecond line".to_string());
+
+    // an empty body is replaced
+    p.notes[0].body.clear();
+    assert!(p.edit_note(1, &None, &Some("only line".to_string()), &None, false, flags).is_ok());
+    assert_eq!(p.notes[0].body, "only line".to_string());
+}
+
 #[test]
 fn test_edit_full_note() {
     let mut p = Profile {
@@ -217,16 +251,15 @@ fn test_edit_full_note() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
     assert!(p.edit_note(1,
-                        &"this is a new title".to_string(),
-                        &["woo body".to_string()],
-                        Some(Status::Started),
+                        &Some("this is a new title".to_string()),
+                        &Some("woo body".to_string()),
+                        &Some(Status::Started),
                         false,
-                        BoolFlags::default())
+                        ProfileFlags::default())
              .is_ok());
     assert_eq!(p.notes[0].id, 1);
     assert_eq!(p.notes[0].title, "this is a new title".to_string());
@@ -244,7 +277,6 @@ fn test_delete_single_note() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     p.delete_note(&[1]);
@@ -261,7 +293,6 @@ fn test_delete_some_notes() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -269,7 +300,6 @@ fn test_delete_some_notes() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 2);
@@ -277,7 +307,6 @@ fn test_delete_some_notes() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 3);
@@ -289,6 +318,33 @@ fn test_delete_some_notes() {
     assert_eq!(p.notes[0].body, "".to_string());
 }
 
+#[test]
+fn test_note_parents() {
+    let mut p = Profile {
+        encrypted: false,
+        notes: vec![],
+    };
+    for title in ["epic", "task", "subtask", "other"] {
+        assert!(p.add_note(title, &[], None, false, false).is_ok());
+    }
+    assert!(p.set_parent(2, Some(1)).is_ok());
+    assert!(p.set_parent(3, Some(2)).is_ok());
+    assert_eq!(p.descendants(&[1]), vec![2, 3]);
+    // a note can't be moved under itself or its children
+    assert!(p.set_parent(1, Some(1)).is_err());
+    assert!(p.set_parent(1, Some(3)).is_err());
+    assert!(p.set_parent(4, Some(5)).is_err());
+    assert!(p.set_parent(3, None).is_ok());
+    assert_eq!(p.notes[2].parent, None);
+    assert!(p.set_parent(3, Some(2)).is_ok());
+
+    // children of a deleted note move up to its parent
+    p.delete_note(&[2]);
+    assert_eq!(p.notes.iter().find(|n| n.id == 3).unwrap().parent, Some(1));
+    p.delete_note(&[1]);
+    assert_eq!(p.notes.iter().find(|n| n.id == 3).unwrap().parent, None);
+}
+
 #[test]
 fn test_clear_notes() {
     let mut p = Profile {
@@ -299,7 +355,6 @@ fn test_clear_notes() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 1);
@@ -307,7 +362,6 @@ fn test_clear_notes() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 2);
@@ -315,7 +369,6 @@ fn test_clear_notes() {
                        &[],
                        Some(Status::Blank),
                        false,
-                       false,
                        false)
              .is_ok());
     assert_eq!(p.notes.len(), 3);
@@ -323,3 +376,80 @@ fn test_clear_notes() {
     assert!(p.clear(true).is_ok());
     assert_eq!(p.notes.len(), 0);
 }
+
+#[test]
+fn test_body_edits() {
+    use theca::item::BodyEdit;
+
+    let body = "alpha\nbeta $5\ngamma\n";
+    assert_eq!(BodyEdit::Append("delta".to_string()).apply(body).ok().unwrap(), "alpha\nbeta $5\ngamma\ndelta");
+    assert_eq!(BodyEdit::Append("delta".to_string()).apply("").ok().unwrap(), "delta");
+    assert_eq!(BodyEdit::Prepend("# head".to_string()).apply(body).ok().unwrap(),
+               "# head\nalpha\nbeta $5\ngamma\n");
+    assert_eq!(BodyEdit::ReplaceLine(3, "GAMMA".to_string()).apply(body).ok().unwrap(), "alpha\nbeta $5\nGAMMA\n");
+    assert!(BodyEdit::ReplaceLine(4, "x".to_string()).apply(body).is_err());
+    assert!(BodyEdit::ReplaceLine(0, "x".to_string()).apply(body).is_err());
+
+    let sed = |expr: &str| BodyEdit::substitution(expr).ok().unwrap().apply(body).ok().unwrap();
+    assert_eq!(sed("s/a/A/"), "Alpha\nbeta $5\ngamma\n");
+    assert_eq!(sed("s/a/A/g"), "AlphA\nbetA $5\ngAmmA\n");
+    assert_eq!(sed("s|(b)ETA|\\1eta &|i"), "alpha\nbeta beta $5\ngamma\n");
+    assert_eq!(sed("s/\\$5/\\/$1/"), "alpha\nbeta /$1\ngamma\n");
+    assert!(BodyEdit::substitution("s/zzz/y/").ok().unwrap().apply(body).is_err());
+    for bad in ["x/a/b/", "s/a/b", "s/a/b/q", "s/(/b/", "s//b/"] {
+        assert!(BodyEdit::substitution(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_due_and_tags() {
+    let (dir, mut session) = temp_session();
+    let mut run = |args: &[&str]| run_in(&dir, &mut session, args).map(|_| session.profile.notes[0].clone());
+
+    let note = run(&["add", "call back", "--due", "2030-01-02T09:30:00+00:00", "--tag", "work", "--tag", " home"]).unwrap();
+    assert_eq!(note.due.as_deref(), Some("2030-01-02 09:30:00 +0000"));
+    assert_eq!(note.tags, vec!["work", "home"]);
+
+    let note = run(&["edit", "1", "--due", "none", "--tag", "phone", "--tag", "work", "--untag", "home"]).unwrap();
+    assert_eq!(note.due, None);
+    assert_eq!(note.tags, vec!["work", "phone"]);
+
+    // nothing changes when a value is wrong
+    assert!(run(&["edit", "1", "--due", "someday", "--title", "x"]).is_err());
+    assert!(run(&["edit", "1", "--tag", "a,b", "--title", "x"]).is_err());
+    assert_eq!(session.profile.notes[0].title, "call back");
+}
+
+#[test]
+fn test_relative_due_date() {
+    let (dir, mut session) = temp_session();
+    let due = (chrono::Utc::now() + chrono::Duration::days(2)).to_rfc3339();
+    run_in(&dir, &mut session, &["add", "renew passport", "--due", &due]).unwrap();
+    let out = run_in(&dir, &mut session,
+                     &["--relative", "--tz", "utc", "--color", "never", "list", "--columns", "id,title,due"]).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0].split_whitespace().collect::<Vec<_>>(), vec!["id", "title", "due"]);
+    assert_eq!(lines[2], "1   renew passport  in 2 days");
+}
+
+#[test]
+fn test_transfer_keeps_due_and_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = Some(dir.path().display().to_string());
+    Profile { encrypted: false, notes: vec![] }.save_to_file("work", &folder, None, true, false, true, &0).unwrap();
+    let mut p = Profile { encrypted: false, notes: vec![] };
+    p.add_note("call back", &[], Some(Status::Started), false, false).unwrap();
+    p.notes[0].due = Some("2030-01-02 09:30:00 +0000".to_string());
+    p.notes[0].tags = vec!["work".to_string(), "phone".to_string()];
+    p.notes[0].created = Some("2020-05-06 07:08:09 +0000".to_string());
+
+    let new_id = p.transfer_note(1, "work", "default", &folder, None, false, true).unwrap();
+    assert!(p.notes.is_empty());
+    let (target, _) = Profile::new("work", &folder, None, false, false, true).unwrap();
+    let note = target.notes.iter().find(|n| n.id == new_id).unwrap();
+    assert_eq!(note.title, "call back");
+    assert_eq!(note.status, Status::Started);
+    assert_eq!(note.created.as_deref(), Some("2020-05-06 07:08:09 +0000"));
+    assert_eq!(note.due.as_deref(), Some("2030-01-02 09:30:00 +0000"));
+    assert_eq!(note.tags, vec!["work", "phone"]);
+}
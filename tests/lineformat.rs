@@ -1,6 +1,6 @@
 extern crate theca;
 
-use theca::item::{Status, Item};
+use theca::item::{Field, Item, Status};
 use theca::lineformat::LineFormat;
 
 struct LineTest {
@@ -25,7 +25,7 @@ fn test_formatter(tests: &[LineTest]) {
 
 #[test]
 fn test_new_line_format_basic() {
-    let basic_tests = vec![LineTest {
+    let basic_tests = [LineTest {
                                input_notes: vec![Item {
                                                      id: 1,
                                                      title: "a title".to_string(),
@@ -33,6 +33,7 @@ fn test_new_line_format_basic() {
                                                      status: Status::Blank,
                                                      last_touched: "2015-01-22 19:43:24 -0800"
                                                                        .to_string(),
+                                                     ..Default::default()
                                                  },
                                                  Item {
                                                      id: 2,
@@ -41,6 +42,7 @@ fn test_new_line_format_basic() {
                                                      status: Status::Blank,
                                                      last_touched: "2015-01-22 19:43:24 -0800"
                                                                        .to_string(),
+                                                     ..Default::default()
                                                  }],
                                condensed: false,
                                search: false,
@@ -50,6 +52,7 @@ fn test_new_line_format_basic() {
                                    title_width: 14,
                                    status_width: 0,
                                    touched_width: 19,
+                                   ..Default::default()
                                },
                            },
                            LineTest {
@@ -60,6 +63,7 @@ fn test_new_line_format_basic() {
                                                      status: Status::Blank,
                                                      last_touched: "2015-01-22 19:43:24 -0800"
                                                                        .to_string(),
+                                                     ..Default::default()
                                                  },
                                                  Item {
                                                      id: 2,
@@ -68,6 +72,7 @@ fn test_new_line_format_basic() {
                                                      status: Status::Blank,
                                                      last_touched: "2015-01-22 19:43:24 -0800"
                                                                        .to_string(),
+                                                     ..Default::default()
                                                  }],
                                condensed: true,
                                search: false,
@@ -77,6 +82,7 @@ fn test_new_line_format_basic() {
                                    title_width: 14,
                                    status_width: 0,
                                    touched_width: 10,
+                                   ..Default::default()
                                },
                            }];
 
@@ -85,7 +91,7 @@ fn test_new_line_format_basic() {
 
 #[test]
 fn test_new_line_format_statuses() {
-    let status_tests = vec![LineTest {
+    let status_tests = [LineTest {
                                 input_notes: vec![Item {
                                                       id: 1,
                                                       title: "a title".to_string(),
@@ -93,6 +99,7 @@ fn test_new_line_format_statuses() {
                                                       status: Status::Started,
                                                       last_touched: "2015-01-22 19:43:24 -0800"
                                                                         .to_string(),
+                                                      ..Default::default()
                                                   },
                                                   Item {
                                                       id: 2,
@@ -101,6 +108,7 @@ fn test_new_line_format_statuses() {
                                                       status: Status::Blank,
                                                       last_touched: "2015-01-22 19:43:24 -0800"
                                                                         .to_string(),
+                                                      ..Default::default()
                                                   }],
                                 condensed: false,
                                 search: false,
@@ -110,6 +118,7 @@ fn test_new_line_format_statuses() {
                                     title_width: 14,
                                     status_width: 7,
                                     touched_width: 19,
+                                    ..Default::default()
                                 },
                             },
                             LineTest {
@@ -120,6 +129,7 @@ fn test_new_line_format_statuses() {
                                                       status: Status::Blank,
                                                       last_touched: "2015-01-22 19:43:24 -0800"
                                                                         .to_string(),
+                                                      ..Default::default()
                                                   },
                                                   Item {
                                                       id: 2,
@@ -128,6 +138,7 @@ fn test_new_line_format_statuses() {
                                                       status: Status::Urgent,
                                                       last_touched: "2015-01-22 19:43:24 -0800"
                                                                         .to_string(),
+                                                      ..Default::default()
                                                   }],
                                 condensed: false,
                                 search: false,
@@ -137,6 +148,7 @@ fn test_new_line_format_statuses() {
                                     title_width: 14,
                                     status_width: 6,
                                     touched_width: 19,
+                                    ..Default::default()
                                 },
                             },
                             LineTest {
@@ -147,6 +159,7 @@ fn test_new_line_format_statuses() {
                                                       status: Status::Blank,
                                                       last_touched: "2015-01-22 19:43:24 -0800"
                                                                         .to_string(),
+                                                      ..Default::default()
                                                   },
                                                   Item {
                                                       id: 2,
@@ -155,6 +168,7 @@ fn test_new_line_format_statuses() {
                                                       status: Status::Urgent,
                                                       last_touched: "2015-01-22 19:43:24 -0800"
                                                                         .to_string(),
+                                                      ..Default::default()
                                                   }],
                                 condensed: true,
                                 search: false,
@@ -164,6 +178,7 @@ fn test_new_line_format_statuses() {
                                     title_width: 14,
                                     status_width: 1,
                                     touched_width: 10,
+                                    ..Default::default()
                                 },
                             }];
 
@@ -172,7 +187,7 @@ fn test_new_line_format_statuses() {
 
 #[test]
 fn test_new_line_format_body() {
-    let body_tests = vec![LineTest {
+    let body_tests = [LineTest {
                               input_notes: vec![Item {
                                                     id: 1,
                                                     title: "a title".to_string(),
@@ -180,6 +195,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -188,6 +204,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: false,
                               search: false,
@@ -197,6 +214,7 @@ fn test_new_line_format_body() {
                                   title_width: 18,
                                   status_width: 0,
                                   touched_width: 19,
+                                  ..Default::default()
                               },
                           },
                           LineTest {
@@ -207,6 +225,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -215,6 +234,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: true,
                               search: false,
@@ -224,6 +244,7 @@ fn test_new_line_format_body() {
                                   title_width: 18,
                                   status_width: 0,
                                   touched_width: 10,
+                                  ..Default::default()
                               },
                           },
                           LineTest {
@@ -234,6 +255,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -242,6 +264,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: false,
                               search: true,
@@ -251,6 +274,7 @@ fn test_new_line_format_body() {
                                   title_width: 14,
                                   status_width: 0,
                                   touched_width: 19,
+                                  ..Default::default()
                               },
                           },
                           LineTest {
@@ -261,6 +285,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -269,6 +294,7 @@ fn test_new_line_format_body() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: true,
                               search: true,
@@ -278,6 +304,7 @@ fn test_new_line_format_body() {
                                   title_width: 14,
                                   status_width: 0,
                                   touched_width: 10,
+                                  ..Default::default()
                               },
                           }];
 
@@ -286,7 +313,7 @@ fn test_new_line_format_body() {
 
 #[test]
 fn test_new_line_format_full() {
-    let body_tests = vec![LineTest {
+    let body_tests = [LineTest {
                               input_notes: vec![Item {
                                                     id: 1,
                                                     title: "a title".to_string(),
@@ -294,6 +321,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Started,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -302,6 +330,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: false,
                               search: false,
@@ -311,6 +340,7 @@ fn test_new_line_format_full() {
                                   title_width: 18,
                                   status_width: 7,
                                   touched_width: 19,
+                                  ..Default::default()
                               },
                           },
                           LineTest {
@@ -321,6 +351,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Started,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -329,6 +360,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: true,
                               search: false,
@@ -338,6 +370,7 @@ fn test_new_line_format_full() {
                                   title_width: 18,
                                   status_width: 1,
                                   touched_width: 10,
+                                  ..Default::default()
                               },
                           },
                           LineTest {
@@ -348,6 +381,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Urgent,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -356,6 +390,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: false,
                               search: true,
@@ -365,6 +400,7 @@ fn test_new_line_format_full() {
                                   title_width: 14,
                                   status_width: 6,
                                   touched_width: 19,
+                                  ..Default::default()
                               },
                           },
                           LineTest {
@@ -375,6 +411,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Blank,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 },
                                                 Item {
                                                     id: 2,
@@ -383,6 +420,7 @@ fn test_new_line_format_full() {
                                                     status: Status::Urgent,
                                                     last_touched: "2015-01-22 19:43:24 -0800"
                                                                       .to_string(),
+                                                    ..Default::default()
                                                 }],
                               condensed: true,
                               search: true,
@@ -392,8 +430,26 @@ fn test_new_line_format_full() {
                                   title_width: 14,
                                   status_width: 1,
                                   touched_width: 10,
+                                  ..Default::default()
                               },
                           }];
 
     test_formatter(&body_tests[..]);
 }
+
+#[test]
+fn test_line_format_with_columns() {
+    let notes = vec![Item {
+                         id: 12,
+                         title: "a title".to_string(),
+                         tags: vec!["work".to_string(), "home".to_string()],
+                         last_touched: "2015-01-22 19:43:24 -0800".to_string(),
+                         ..Default::default()
+                     }];
+    let columns = [Field::Id, Field::Tags, Field::Due];
+    let format = LineFormat::with_columns(&notes, &columns, false, false).ok().unwrap();
+    assert_eq!(format.tags_width, 9);
+    assert_eq!(format.due_width, 19);
+    assert_eq!(format.line_width(), 2 + 9 + 19 + 2 * 2);
+    assert!(LineFormat::with_columns(&notes, &[Field::Id, Field::Body], false, false).is_err());
+}
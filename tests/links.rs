@@ -0,0 +1,40 @@
+extern crate theca;
+
+use std::collections::HashMap;
+
+use theca::item::Item;
+use theca::links::{backlinks, broken, from_markdown, links, outgoing, to_markdown, LinkTargets};
+
+fn note(id: usize, title: &str, body: &str) -> Item {
+    Item { id, title: title.to_string(), body: body.to_string(), ..Default::default() }
+}
+
+#[test]
+fn test_links() {
+    let notes = vec![note(1, "Epic", "see [[2]] and [[ release plan ]]\n```\n[[3]]\n```"),
+                     note(2, "Release plan", "back to [[epic]], [[Missing]]"),
+                     note(3, "3", "")];
+    assert_eq!(links(&notes[0].body), vec!["2", "release plan"]);
+    assert_eq!(outgoing(&notes[1], &notes).iter().map(|l| l.to).collect::<Vec<_>>(), vec![Some(1), None]);
+    assert_eq!(backlinks(2, &notes).iter().map(|n| n.id).collect::<Vec<_>>(), vec![1]);
+    let broken = broken(&notes);
+    assert_eq!(broken.len(), 1);
+    assert_eq!((broken[0].from, broken[0].text.as_str()), (2, "Missing"));
+}
+
+#[test]
+fn test_mirror_links_round_trip() {
+    let notes = vec![note(1, "Epic", ""), note(2, "Release plan", "")];
+    let paths = HashMap::from([(1, "Done/1-Epic.md".to_string()), (2, "2-Release plan.md".to_string())]);
+    let targets = LinkTargets::new(&notes, paths);
+    let body = "[[1]], [[release plan]] and [[Missing]], [other](2-Release%20plan.md)";
+
+    let markdown = to_markdown(body, "Started/3-x.md", &targets);
+    assert_eq!(markdown,
+               "[1](../Done/1-Epic.md), [release plan](../2-Release%20plan.md) and [[Missing]], \
+                [other](2-Release%20plan.md)");
+
+    let files = HashMap::from([("Done/1-Epic.md".to_string(), (1, Some("Epic".to_string()))),
+                               ("2-Release plan.md".to_string(), (2, Some("Release plan".to_string())))]);
+    assert_eq!(from_markdown(&markdown, "Started/3-x.md", &files), body);
+}
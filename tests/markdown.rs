@@ -0,0 +1,27 @@
+extern crate theca;
+
+use theca::markdown::render_markdown;
+
+#[test]
+fn test_render_plain_markdown() {
+    let body = "# Title\n\nsome *text* with `code` and a [link](http://x.y)\n\n- [ ] todo\n- [x] done\n- item\n";
+    assert_eq!(render_markdown(body, 0, false).ok().unwrap(),
+               "# Title\n\nsome text with code and a link (http://x.y)\n\n[ ] todo\n[x] done\n• item\n"
+                   .to_string());
+}
+
+#[test]
+fn test_render_wraps_and_indents() {
+    let body = "1. one two three four five six\n\n> a quoted line that wraps\n\n```\nlet x = 1;\n```\n";
+    assert_eq!(render_markdown(body, 16, false).ok().unwrap(),
+               "1. one two three\n   four five six\n\n│ a quoted line\n│ that wraps\n\n    let x = 1;\n"
+                   .to_string());
+}
+
+#[test]
+fn test_render_styles_only_with_color() {
+    let plain = render_markdown("**bold**", 0, false).ok().unwrap();
+    let styled = render_markdown("**bold**", 0, true).ok().unwrap();
+    assert_eq!(plain, "bold\n".to_string());
+    assert!(styled.contains("\x1b[") && styled.contains("bold"));
+}
@@ -0,0 +1,42 @@
+extern crate theca;
+
+use std::collections::HashMap;
+
+use theca::item::Status;
+use theca::notetemplate::{NoteTemplate, Placeholders};
+
+#[test]
+fn test_note_template() {
+    let template = NoteTemplate::parse("---\ntitle: 'Bug: {title}'\nstatus: urgent\ntags: [bug, '{ask:area}']\n---\n\
+                                        # {title} in {profile}\n\nseen in {ask:version} on {date:%Y}, {{not}} \
+                                        for {ask:area}\n")
+                       .ok()
+                       .unwrap();
+    assert_eq!(template.variables().ok().unwrap(), vec!["area", "version"]);
+    let values = Placeholders {
+        title: "crash on save".to_string(),
+        profile: "work".to_string(),
+        vars: HashMap::from([("area".to_string(), "sync".to_string()), ("version".to_string(), "2.0".to_string())]),
+        ..Default::default()
+    };
+    let note = template.fill(&values).ok().unwrap();
+    assert_eq!(note.title, "Bug: crash on save");
+    assert_eq!(note.status, Some(Status::Urgent));
+    assert_eq!(note.tags, vec!["bug", "sync"]);
+    assert!(note.body.starts_with("# crash on save in work\n\nseen in 2.0 on "));
+    assert!(note.body.ends_with(", {not} for sync\n"));
+
+    // without frontmatter the title given to add is kept
+    let plain = NoteTemplate::parse("{time}").ok().unwrap();
+    let note = plain.fill(&values).ok().unwrap();
+    assert_eq!((note.title.as_str(), note.status, note.body.len()), ("crash on save", None, 5));
+}
+
+#[test]
+fn test_note_template_errors() {
+    assert!(NoteTemplate::parse("{nope}").is_err());
+    assert!(NoteTemplate::parse("a { brace").is_err());
+    assert!(NoteTemplate::parse("{date:%Q}").is_err());
+    assert!(NoteTemplate::parse("---\ndue: tomorrow\n---\nbody").is_err());
+    assert!(NoteTemplate::parse("---\ntitle: {title}\n---\nbody").is_err());
+}
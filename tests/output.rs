@@ -0,0 +1,68 @@
+extern crate theca;
+
+use theca::ProfileFlags;
+use theca::item::{Item, Status};
+use theca::output::{write_notes, write_note, OutputFormat};
+use theca::utils::DateDisplay;
+
+fn note() -> Item {
+    Item {
+        id: 1,
+        title: "a title".to_string(),
+        body: "".to_string(),
+        status: Status::Started,
+        last_touched: "2015-01-22 19:43:24 -0800".to_string(),
+        ..Default::default()
+    }
+}
+
+fn render_notes(notes: &[Item], format: OutputFormat) -> String {
+    let dates = DateDisplay { tz: "-08:00".parse().ok().unwrap(), relative: false };
+    let flags = ProfileFlags { format, dates, ..Default::default() };
+    let mut out: Vec<u8> = vec![];
+    assert!(write_notes(&mut out, notes, &flags, "this profile is empty").is_ok());
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_empty_notes() {
+    assert_eq!(render_notes(&[], OutputFormat::Json), "[]\n".to_string());
+    assert_eq!(render_notes(&[], OutputFormat::Yaml), "[]\n".to_string());
+    assert_eq!(render_notes(&[], OutputFormat::Ndjson), "".to_string());
+    assert_eq!(render_notes(&[], OutputFormat::Text), "this profile is empty\n".to_string());
+}
+
+#[test]
+fn test_ndjson_notes() {
+    let out = render_notes(&[note(), note()], OutputFormat::Ndjson);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0],
+               "{\"id\":1,\"title\":\"a title\",\"status\":\"Started\",\"body\":\"\",\
+                \"last_touched\":\"2015-01-22T19:43:24-08:00\"}");
+}
+
+#[test]
+fn test_json_note() {
+    let flags = ProfileFlags { format: OutputFormat::Json, ..Default::default() };
+    let mut out: Vec<u8> = vec![];
+    assert!(write_note(&mut out, &note(), &[], &flags).is_ok());
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("{\n"));
+    assert!(out.contains("\"title\": \"a title\""));
+}
+
+#[test]
+fn test_ndjson_optional_fields() {
+    let child = Item {
+        body: "- [x] one\n- [ ] two".to_string(),
+        tags: vec!["work".to_string()],
+        parent: Some(3),
+        ..note()
+    };
+    let out = render_notes(&[child], OutputFormat::Ndjson);
+    assert_eq!(out,
+               "{\"id\":1,\"title\":\"a title\",\"status\":\"Started\",\"body\":\"- [x] one\\n- [ ] two\",\
+                \"last_touched\":\"2015-01-22T19:43:24-08:00\",\"tags\":[\"work\"],\"parent\":3,\
+                \"progress\":{\"checked\":1,\"total\":2}}\n");
+}
@@ -0,0 +1,67 @@
+extern crate theca;
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use theca::item::Item;
+use theca::shell::{ShellCompleter, HISTORY_FILE};
+use theca::Profile;
+
+fn completions(completer: &ShellCompleter, line: &str) -> (usize, Vec<String>) {
+    let (start, pairs) = completer.candidates(line, line.len());
+    (start, pairs.into_iter().map(|p| p.replacement).collect())
+}
+
+#[test]
+fn test_shell_completion() {
+    let notes = vec![Item { id: 3, title: "fix bug".to_string(), ..Default::default() },
+                     Item { id: 12, title: "write docs".to_string(), ..Default::default() }];
+    let completer = ShellCompleter::new(&notes, vec!["default".to_string(), "work".to_string()]);
+
+    assert_eq!(completions(&completer, "ed"), (0, vec!["edit".to_string()]));
+    assert_eq!(completions(&completer, "edit 1"), (5, vec!["12".to_string()]));
+    // ids can be found by their title
+    assert_eq!(completions(&completer, "del 3 DOC"), (6, vec!["12".to_string()]));
+    assert_eq!(completions(&completer, "transfer 3 w"), (11, vec!["work".to_string()]));
+    assert_eq!(completions(&completer, "search \"fix b"), (7, vec!["'fix bug'".to_string()]));
+    assert!(completions(&completer, "list --limit ").1.is_empty());
+}
+
+// run the shell binary on a profile folder with `input` as its lines
+fn run_shell(folder: &Path, args: &[&str], input: &str) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_theca"))
+                        .arg("--profile-folder")
+                        .arg(folder)
+                        .args(args)
+                        .arg("shell")
+                        .env("THECA_CONFIG", folder.join("user.yaml"))
+                        .env_remove("THECA_KEY")
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::null())
+                        .spawn()
+                        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_shell_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = Some(dir.path().display().to_string());
+    let key = "secret key".to_string();
+    Profile { encrypted: true, notes: vec![] }
+        .save_to_file("vault", &folder, Some(&key), true, false, true, &0)
+        .unwrap();
+    Profile { encrypted: false, notes: vec![] }.save_to_file("plain", &folder, None, true, false, true, &0).unwrap();
+
+    // the lines of an encrypted session would give its notes away
+    run_shell(dir.path(), &["--profile", "vault", "--encrypted", "--key", &key], "add \"secret title\"\nexit\n");
+    assert!(!dir.path().join(HISTORY_FILE).exists());
+    let (vault, _) = Profile::new("vault", &folder, Some(&key), false, true, true).unwrap();
+    assert_eq!(vault.notes[0].title, "secret title");
+
+    run_shell(dir.path(), &["--profile", "plain"], "add \"plain title\"\nexit\n");
+    let history = std::fs::read_to_string(dir.path().join(HISTORY_FILE)).unwrap();
+    assert!(history.contains("add \"plain title\""));
+}
@@ -0,0 +1,114 @@
+extern crate theca;
+
+use theca::frontmatter::{render, Frontmatter};
+use theca::item::{Item, Status};
+use theca::sync::{note_hash, plan, Action, GroupBy, MdFile, Mirror, SyncEntry, SyncPrefer, SyncState};
+
+fn note(id: usize, title: &str, body: &str) -> Item {
+    Item {
+        id,
+        title: title.to_string(),
+        body: body.to_string(),
+        last_touched: "2015-01-22 19:43:24 +0000".to_string(),
+        ..Default::default()
+    }
+}
+
+fn file(name: &str, n: &Item) -> MdFile {
+    MdFile::new(name, &render(&Frontmatter::from_item(n), &n.body).ok().unwrap())
+}
+
+fn synced(notes: &[&Item]) -> SyncState {
+    let mut state = SyncState::default();
+    for n in notes {
+        state.notes.insert(n.id, SyncEntry { hash: note_hash(n), file: format!("{}-{}.md", n.id, n.title) });
+    }
+    state
+}
+
+#[test]
+fn test_sync_plan_tells_deleted_from_never_exported() {
+    let (a, b) = (note(1, "a", "x"), note(2, "b", "y"));
+    let state = synced(&[&a]);
+    let actions = plan(&[a, b], &[], &state, &Mirror::default(), None);
+    assert_eq!(actions,
+               vec![Action::DeleteNote { id: 1 },
+                    Action::Export { id: 2, from: None, to: "2-b.md".to_string() }]);
+}
+
+#[test]
+fn test_sync_plan_one_side_changed() {
+    let (a, b) = (note(1, "a", "x"), note(2, "b", "y"));
+    let state = synced(&[&a, &b]);
+    let a_edited = note(1, "a", "edited in the profile");
+    let b_edited = note(2, "b", "edited in the file");
+    let files = [file("1-a.md", &a), file("2-b.md", &b_edited)];
+    let actions = plan(&[a_edited, b], &files, &state, &Mirror::default(), None);
+    assert_eq!(actions,
+               vec![Action::Export { id: 1, from: Some("1-a.md".to_string()), to: "1-a.md".to_string() },
+                    Action::Import { id: 2, file: "2-b.md".to_string() }]);
+}
+
+#[test]
+fn test_sync_plan_conflicts() {
+    let (a, b) = (note(1, "a", "x"), note(2, "b", "y"));
+    let state = synced(&[&a, &b]);
+    let files = [file("1-a.md", &note(1, "a", "file")), file("2-b.md", &note(2, "b", "file"))];
+    let notes = [note(1, "a", "profile")];
+    let actions = plan(&notes, &files, &state, &Mirror::default(), None);
+    assert!(matches!(actions[0], Action::Conflict { id: 1, .. }));
+    assert!(matches!(actions[1], Action::Conflict { id: 2, .. }));
+
+    let actions = plan(&notes, &files, &state, &Mirror::default(), Some(SyncPrefer::Files));
+    assert_eq!(actions,
+               vec![Action::Import { id: 1, file: "1-a.md".to_string() },
+                    Action::Create { file: "2-b.md".to_string() }]);
+}
+
+#[test]
+fn test_sync_plan_keeps_unknown_files() {
+    let a = note(1, "a", "x");
+    let state = synced(&[&a]);
+    let files = [file("1-a.md", &a), MdFile::new("notes.md", "# plain markdown\n")];
+    let actions = plan(&[], &files, &state, &Mirror::default(), None);
+    assert_eq!(actions,
+               vec![Action::RemoveFile { id: 1, file: "1-a.md".to_string() },
+                    Action::Create { file: "notes.md".to_string() }]);
+}
+
+#[test]
+fn test_mirror_layout() {
+    let mut a = note(3, "fix: bug", "x");
+    a.status = Status::Urgent;
+    a.tags = vec!["work".to_string()];
+    a.created = Some("2015-01-20 10:00:00 +0000".to_string());
+    assert_eq!(Mirror::default().path(&a), "3-fix_ bug.md");
+
+    let mirror = Mirror {
+        filename: "{created} {title} ({id})".to_string(),
+        group_by: Some(GroupBy::Status),
+        ..Default::default()
+    };
+    assert!(mirror.validate().is_ok());
+    assert_eq!(mirror.path(&a), "urgent/2015-01-20 fix_ bug (3).md");
+    let tagged = Mirror { group_by: Some(GroupBy::Tag), ..Default::default() };
+    assert_eq!(tagged.path(&a), "work/3-fix_ bug.md");
+
+    // a file moved by status follows the note, one the user named doesn't
+    let state = synced(&[&a]);
+    a.status = Status::Done;
+    let files = [file("urgent/2015-01-20 fix_ bug (3).md", &a)];
+    assert_eq!(plan(&[a.clone()], &files, &state, &mirror, None),
+               vec![Action::Export {
+                        id: 3,
+                        from: Some("urgent/2015-01-20 fix_ bug (3).md".to_string()),
+                        to: "done/2015-01-20 fix_ bug (3).md".to_string(),
+                    }]);
+    let files = [file("urgent/my bug.md", &a)];
+    assert_eq!(plan(&[a], &files, &state, &mirror, None),
+               vec![Action::Unchanged { id: 3, file: "urgent/my bug.md".to_string() }]);
+
+    for bad in ["{id}/{title}", "{nope}", "{id"] {
+        assert!(Mirror { filename: bad.to_string(), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,54 @@
+extern crate theca;
+
+use theca::item::{Item, Status};
+use theca::template::Template;
+
+fn note(body: &str, status: Status) -> Item {
+    Item {
+        id: 7,
+        title: "a rather long title".to_string(),
+        body: body.to_string(),
+        status,
+        last_touched: "2015-01-22 19:43:24 -0800".to_string(),
+        ..Default::default()
+    }
+}
+
+fn render(template: &str, item: &Item) -> String {
+    template.parse::<Template>().ok().unwrap().render(item, false).ok().unwrap()
+}
+
+#[test]
+fn test_template_fields_and_padding() {
+    let n = note("", Status::Urgent);
+    assert_eq!(render("{id:>4}|{status:1}|{title:.10}|{title:<3}", &n),
+               "   7|U|a rathe...|a r".to_string());
+    assert_eq!(render("{status:^8}|{status:upper}", &n), " Urgent |URGENT".to_string());
+    assert_eq!(render("{{{id}}}", &n), "{7}".to_string());
+}
+
+#[test]
+fn test_template_conditionals() {
+    let with_body = note("first\nsecond", Status::Blank);
+    let without = note("", Status::Started);
+    let t = "{title:.5}{?body} (+) {body:firstline}{/}{!status} -{/}";
+    assert_eq!(render(t, &with_body), "a ... (+) first -".to_string());
+    assert_eq!(render(t, &without), "a ...".to_string());
+}
+
+#[test]
+fn test_template_styles_only_with_color() {
+    let n = note("", Status::Blank);
+    let t: Template = "{#red}{id}".parse().ok().unwrap();
+    assert_eq!(t.render(&n, false).ok().unwrap(), "7".to_string());
+    assert!(t.render(&n, true).ok().unwrap().starts_with("\x1b["));
+}
+
+#[test]
+fn test_template_errors() {
+    assert!("{nope}".parse::<Template>().is_err());
+    assert!("{?body}abc".parse::<Template>().is_err());
+    assert!("abc{/}".parse::<Template>().is_err());
+    assert!("{id:wide}".parse::<Template>().is_err());
+    assert!("{#sparkly}".parse::<Template>().is_err());
+}
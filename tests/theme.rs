@@ -0,0 +1,27 @@
+extern crate theca;
+
+use theca::config::Config;
+use theca::item::Status;
+use theca::theme::{paint, style_ansi, ColorChoice, Theme};
+
+#[test]
+fn test_styles() {
+    assert_eq!(style_ansi("").ok().unwrap(), "".to_string());
+    assert_eq!(paint("x", "").ok().unwrap(), "x".to_string());
+    assert_eq!(paint("x", "bold").ok().unwrap(), "\x1b[1mx\x1b[0m".to_string());
+    assert!(style_ansi("bold sparkly").is_err());
+    assert!(!ColorChoice::Never.enabled());
+    assert!(ColorChoice::Always.enabled());
+}
+
+#[test]
+fn test_partial_theme_from_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.yaml");
+    std::fs::write(&path, "theme:\n  urgent: bold magenta\n").unwrap();
+    let theme = Config::load(&path).ok().unwrap().theme.unwrap();
+    assert_eq!(theme.status(Status::Urgent), "bold magenta");
+    assert_eq!(theme.status(Status::Started), Theme::default().started);
+    assert_eq!(theme.status(Status::Blank), "");
+    assert!(theme.validate().is_ok());
+}
@@ -0,0 +1,15 @@
+extern crate theca;
+
+use theca::item::Item;
+use theca::tui::filter_notes;
+
+#[test]
+fn test_filter_notes() {
+    let notes = vec![Item { id: 1, title: "Groceries".to_string(), ..Default::default() },
+                     Item { id: 2, title: "a".to_string(), body: "buy MILK".to_string(), ..Default::default() },
+                     Item { id: 3, title: "b".to_string(), tags: vec!["milkman".to_string()], ..Default::default() }];
+    assert_eq!(filter_notes(&notes, ""), vec![0, 1, 2]);
+    assert_eq!(filter_notes(&notes, "milk"), vec![1, 2]);
+    assert_eq!(filter_notes(&notes, "GROC"), vec![0]);
+    assert!(filter_notes(&notes, "nothing").is_empty());
+}
@@ -1,16 +1,31 @@
 extern crate theca;
 
-use theca::utils::{cmp_last_touched, format_field};
+use theca::ProfileFlags;
+use theca::item::{Item, SortKey, Status};
+use theca::utils::{cmp_last_touched, extract_status, format_field, is_piped, normalize_timestamp, output_height,
+                   parse_due, relative_time, sort_notes, tree_order,
+                   DateDisplay, DisplayTz};
 use std::cmp::Ordering;
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
 
 #[test]
 fn test_format_field() {
-    assert_eq!(format_field(&"this is some stuff yo".to_string(), 12, false),
+    assert_eq!(format_field("this is some stuff yo", 12, false),
                "this is some".to_string());
-    assert_eq!(format_field(&"this is some stuff yo".to_string(), 11, true),
+    assert_eq!(format_field("this is some stuff yo", 11, true),
                "this is ...".to_string());
 }
 
+#[test]
+fn test_extract_status() {
+    assert_eq!(extract_status(Some("done".to_string())).ok().unwrap(), Some(Status::Done));
+    assert_eq!(extract_status(Some("Urgent".to_string())).ok().unwrap(), Some(Status::Urgent));
+    assert_eq!(extract_status(None).ok().unwrap(), None);
+    assert!(extract_status(Some("later".to_string())).is_err());
+}
+
 #[test]
 fn test_cmp_last_touched() {
     let old = "2015-01-22 19:43:24 -0800";
@@ -22,3 +37,98 @@ fn test_cmp_last_touched() {
     assert_eq!(cmp_last_touched(old, new).ok().unwrap(), Ordering::Less);
     assert_eq!(cmp_last_touched(new, old).ok().unwrap(), Ordering::Greater);
 }
+
+#[test]
+fn test_relative_time() {
+    let now = chrono::DateTime::parse_from_rfc3339("2015-01-26T20:00:00-08:00").unwrap();
+    let at = |s: &str| chrono::DateTime::parse_from_rfc3339(s).unwrap();
+
+    assert_eq!(relative_time(at("2015-01-26T19:59:30-08:00"), now), "just now".to_string());
+    assert_eq!(relative_time(at("2015-01-26T17:00:00-08:00"), now), "3h ago".to_string());
+    assert_eq!(relative_time(at("2015-01-25T09:00:00-08:00"), now), "yesterday".to_string());
+    assert_eq!(relative_time(at("2015-01-28T09:00:00-08:00"), now), "in 2 days".to_string());
+    assert_eq!(relative_time(at("2014-11-20T09:00:00-08:00"), now), "2 months ago".to_string());
+}
+
+#[test]
+fn test_sort_notes_by_keys() {
+    let note = |id: usize, title: &str, status: Status, touched: &str| Item {
+        id,
+        title: title.to_string(),
+        status,
+        last_touched: touched.to_string(),
+        ..Default::default()
+    };
+    let mut notes = vec![note(1, "b", Status::Done, "2015-01-22 19:43:24 -0800"),
+                         note(2, "a", Status::Blank, "2015-01-22 19:43:24 -0800"),
+                         note(3, "c", Status::Urgent, "2015-01-20 19:43:24 -0800"),
+                         note(4, "d", Status::Blank, "2015-01-25 19:43:24 -0800"),
+                         note(5, "e", Status::Started, "2015-01-21 19:43:24 -0800")];
+    let flags = ProfileFlags {
+        sort: SortKey::parse_list("status,-touched,title").ok().unwrap(),
+        ..Default::default()
+    };
+    sort_notes(&mut notes, 0, &flags, None);
+    assert_eq!(notes.iter().map(|n| n.id).collect::<Vec<usize>>(), vec![3, 5, 4, 2, 1]);
+    assert!(SortKey::parse_list("status,-nope").is_err());
+}
+
+#[test]
+fn test_tree_order() {
+    let note = |id: usize, parent: Option<usize>| Item { id, parent, ..Default::default() };
+    // 6's parent isn't listed, 7 and 8 are each other's parents
+    let notes = vec![note(1, None), note(2, Some(4)), note(3, Some(1)), note(4, Some(1)), note(5, None),
+                     note(6, Some(9)), note(7, Some(8)), note(8, Some(7))];
+    let ordered = tree_order(&notes);
+    assert_eq!(ordered.iter().map(|n| n.id).collect::<Vec<usize>>(), vec![1, 3, 4, 2, 5, 6, 7, 8]);
+}
+
+#[test]
+fn test_date_display() {
+    let berlin = DateDisplay { tz: "Europe/Berlin".parse().ok().unwrap(), relative: false };
+    assert_eq!(berlin.format("2015-01-22 19:43:24 -0800").ok().unwrap(),
+               "2015-01-23 04:43:24".to_string());
+    let offset = DateDisplay { tz: "+02:00".parse().ok().unwrap(), relative: false };
+    assert_eq!(offset.rfc3339("2015-01-22 19:43:24 -0800").ok().unwrap(),
+               "2015-01-23T05:43:24+02:00".to_string());
+    assert!("Nowhere/Special".parse::<DisplayTz>().is_err());
+}
+
+#[test]
+fn test_normalize_timestamp() {
+    assert_eq!(normalize_timestamp("2015-01-22 19:43:24 -0800"),
+               "2015-01-23 03:43:24 +0000".to_string());
+    assert_eq!(normalize_timestamp("2015-01-23T03:43:24Z"),
+               "2015-01-23 03:43:24 +0000".to_string());
+    assert_eq!(normalize_timestamp("not a date"), "not a date".to_string());
+}
+
+#[test]
+fn test_output_height() {
+    assert_eq!(output_height("", 80), 0);
+    assert_eq!(output_height("one\n\nthree\n", 80), 3);
+    assert_eq!(output_height(&"x".repeat(25), 10), 3);
+    assert_eq!(output_height(&"x".repeat(25), 0), 1);
+}
+
+#[test]
+fn test_is_piped() {
+    let file = tempfile::tempfile().unwrap();
+    assert!(is_piped(file.as_raw_fd()));
+    // what cron and service managers leave on stdin
+    assert!(!is_piped(File::open("/dev/null").unwrap().as_raw_fd()));
+    let (socket, _other) = UnixStream::pair().unwrap();
+    assert!(!is_piped(socket.as_raw_fd()));
+}
+
+#[test]
+fn test_parse_due() {
+    assert_eq!(parse_due("2030-01-02T09:30:00+02:00").ok().unwrap().as_deref(), Some("2030-01-02 07:30:00 +0000"));
+    assert_eq!(parse_due("none").ok().unwrap(), None);
+    // dates without a time zone are local, they are stored in UTC
+    for local in ["2030-01-02", "2030-01-02 09:30"] {
+        assert!(parse_due(local).ok().unwrap().unwrap().ends_with(" +0000"));
+    }
+    assert!(parse_due("tomorrow").is_err());
+    assert!(parse_due("2030-13-02").is_err());
+}